@@ -0,0 +1,98 @@
+//! # EnvType derive crate
+//!
+//! env-type-derive crate provides derive macros for the env-type crate.
+//! Use it through the `derive` feature of env-type.
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// Derive the EnvKey trait for a unit struct.
+/// The key is given by the `#[env_key("NAME")]` attribute,
+/// or the SCREAMING_SNAKE_CASE of the type name if omitted, e.g. `PaymentsEnv` is `PAYMENTS_ENV`.
+#[proc_macro_derive(EnvKey, attributes(env_key))]
+pub fn derive_env_key(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_env_key(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_env_key(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    match &input.data {
+        Data::Struct(data) if matches!(data.fields, Fields::Unit) => {}
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "EnvKey can only be derived for unit structs",
+            ))
+        }
+    }
+
+    let mut key = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("env_key"))
+    {
+        if key.is_some() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "duplicate #[env_key] attribute",
+            ));
+        }
+        let name: LitStr = attr.parse_args().map_err(|err| {
+            syn::Error::new(
+                err.span(),
+                "expected #[env_key(\"NAME\")] with a string literal",
+            )
+        })?;
+        if name.value().is_empty() {
+            return Err(syn::Error::new_spanned(name, "env_key must not be empty"));
+        }
+        key = Some(name.value());
+    }
+    let key = key.unwrap_or_else(|| screaming_snake_case(&input.ident.to_string()));
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::env_type::types::EnvKey for #ident #ty_generics #where_clause {
+            fn key() -> &'static str {
+                #key
+            }
+        }
+    })
+}
+
+/// Convert a type name to SCREAMING_SNAKE_CASE, e.g. `HTTPServerEnv` is `HTTP_SERVER_ENV`.
+fn screaming_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut converted = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if i > 0 && c.is_uppercase() {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lower)
+            {
+                converted.push('_');
+            }
+        }
+        converted.extend(c.to_uppercase());
+    }
+    converted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_screaming_snake_case() {
+        assert_eq!(screaming_snake_case("PaymentsEnv"), "PAYMENTS_ENV");
+        assert_eq!(screaming_snake_case("Env"), "ENV");
+        assert_eq!(screaming_snake_case("HTTPServerEnv"), "HTTP_SERVER_ENV");
+        assert_eq!(screaming_snake_case("Service2Env"), "SERVICE2_ENV");
+    }
+}
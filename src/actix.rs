@@ -0,0 +1,186 @@
+//! This is used to share the environment with actix-web handlers as app data.
+//! The CurrentEnv and EnvValue extractors read it, and IsDebugGuard matches routes only in debug environments.
+//!
+//! # Example
+//!
+//! ```
+//! use actix_web::{web, App};
+//! use env_type::actix::{environment_data, CurrentEnv, EnvValue, IsDebugGuard};
+//! use env_type::context::{ContextBuilder, ContextMarker};
+//! use env_type::environment::EnvironmentBuilder;
+//! use env_type::types::EnvType;
+//!
+//! struct UrlContext;
+//!
+//! impl ContextMarker for UrlContext {
+//!     type Value = String;
+//! }
+//!
+//! async fn handler(CurrentEnv(env): CurrentEnv, EnvValue(url): EnvValue<UrlContext>) -> String {
+//!     format!("{env:?}: {url}")
+//! }
+//!
+//! async fn debug_handler() -> &'static str {
+//!     "debug"
+//! }
+//!
+//! let env = EnvironmentBuilder::default()
+//!     .current_env(EnvType::Prod)
+//!     .with_context(
+//!         ContextBuilder::<UrlContext>::default()
+//!             .with_value(EnvType::Prod, "https://example.com".to_string())
+//!             .build(),
+//!     )
+//!     .build()
+//!     .unwrap();
+//! let app = App::new()
+//!     .app_data(environment_data(env))
+//!     .route("/", web::get().to(handler))
+//!     .route("/debug", web::get().guard(IsDebugGuard).to(debug_handler));
+//! ```
+use crate::context::ContextMarker;
+use crate::environment::Environment;
+use crate::is_debug::IsDebug;
+use crate::types::EnvType;
+use actix_web::dev::Payload;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::guard::{Guard, GuardContext};
+use actix_web::web::Data;
+use actix_web::{Error, FromRequest, HttpRequest};
+use std::future::{ready, Ready};
+use std::sync::Arc;
+
+/// Wrap the environment, or the environment already shared by Arc, to register it with App::app_data.
+pub fn environment_data<E: Into<Arc<Environment>>>(env: E) -> Data<Environment> {
+    Data::from(env.into())
+}
+
+/// Get the environment registered by environment_data, or fail with 500 Internal Server Error.
+fn environment(req: &HttpRequest) -> Result<&Data<Environment>, Error> {
+    req.app_data::<Data<Environment>>().ok_or_else(|| {
+        ErrorInternalServerError("environment is not found, register it with environment_data")
+    })
+}
+
+/// CurrentEnv is an extractor of the current environment type.
+pub struct CurrentEnv(pub EnvType);
+
+impl FromRequest for CurrentEnv {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(environment(req).map(|env| Self(env.current_env())))
+    }
+}
+
+/// EnvValue is an extractor of the current value for the context marker.
+/// It fails with 500 Internal Server Error naming the context marker if the value is missing.
+pub struct EnvValue<M: ContextMarker>(pub M::Value);
+
+impl<M: ContextMarker> FromRequest for EnvValue<M> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(environment(req).and_then(|env| {
+            env.current_value::<M>().map(Self).ok_or_else(|| {
+                ErrorInternalServerError(format!(
+                    "context value not found for {} in {:?}",
+                    std::any::type_name::<M>(),
+                    env.current_env()
+                ))
+            })
+        }))
+    }
+}
+
+/// IsDebugGuard is a route guard matching only if the registered environment is a debug environment.
+/// Routes are not matched if the environment is not registered.
+pub struct IsDebugGuard;
+
+impl Guard for IsDebugGuard {
+    fn check(&self, ctx: &GuardContext<'_>) -> bool {
+        ctx.app_data::<Data<Environment>>()
+            .is_some_and(|env| env.is_debug())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::ContextBuilder;
+    use crate::environment::EnvironmentBuilder;
+    use crate::is_debug::debug_context;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, web, App};
+
+    struct UrlContext;
+
+    impl ContextMarker for UrlContext {
+        type Value = String;
+    }
+
+    async fn handler(CurrentEnv(env): CurrentEnv, EnvValue(url): EnvValue<UrlContext>) -> String {
+        format!("{env:?}: {url}")
+    }
+
+    async fn debug_handler() -> &'static str {
+        "debug"
+    }
+
+    fn environment(current: EnvType) -> Environment {
+        EnvironmentBuilder::default()
+            .current_env(current)
+            .with_context(debug_context().build())
+            .with_context(
+                ContextBuilder::<UrlContext>::default()
+                    .with_value(EnvType::Prod, "https://example.com".to_string())
+                    .build(),
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn test_extractors() {
+        for (current, status, body) in [
+            (EnvType::Prod, StatusCode::OK, "Prod: https://example.com"),
+            (
+                EnvType::Dev,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "UrlContext",
+            ),
+        ] {
+            let app = test::init_service(
+                App::new()
+                    .app_data(environment_data(environment(current)))
+                    .route("/", web::get().to(handler)),
+            )
+            .await;
+            let response =
+                test::call_service(&app, test::TestRequest::get().uri("/").to_request()).await;
+            assert_eq!(response.status(), status);
+            let actual = test::read_body(response).await;
+            assert!(String::from_utf8_lossy(&actual).contains(body));
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_is_debug_guard() {
+        for (current, status) in [
+            (EnvType::Dev, StatusCode::OK),
+            (EnvType::Prod, StatusCode::NOT_FOUND),
+        ] {
+            let app = test::init_service(
+                App::new()
+                    .app_data(environment_data(environment(current)))
+                    .route("/debug", web::get().guard(IsDebugGuard).to(debug_handler)),
+            )
+            .await;
+            let response =
+                test::call_service(&app, test::TestRequest::get().uri("/debug").to_request()).await;
+            assert_eq!(response.status(), status);
+        }
+    }
+}
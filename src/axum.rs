@@ -0,0 +1,227 @@
+//! This is used to share the environment with axum handlers through the request extensions.
+//! EnvironmentLayer injects the shared environment, and the CurrentEnv and EnvValue extractors read it.
+//!
+//! # Example
+//!
+//! ```
+//! use axum::{routing::get, Router};
+//! use env_type::axum::{CurrentEnv, EnvValue, EnvironmentLayer};
+//! use env_type::context::{ContextBuilder, ContextMarker};
+//! use env_type::environment::EnvironmentBuilder;
+//! use env_type::types::EnvType;
+//!
+//! struct UrlContext;
+//!
+//! impl ContextMarker for UrlContext {
+//!     type Value = String;
+//! }
+//!
+//! async fn handler(CurrentEnv(env): CurrentEnv, EnvValue(url): EnvValue<UrlContext>) -> String {
+//!     format!("{env:?}: {url}")
+//! }
+//!
+//! let env = EnvironmentBuilder::default()
+//!     .current_env(EnvType::Prod)
+//!     .with_context(
+//!         ContextBuilder::<UrlContext>::default()
+//!             .with_value(EnvType::Prod, "https://example.com".to_string())
+//!             .build(),
+//!     )
+//!     .build()
+//!     .unwrap();
+//! let app: Router = Router::new()
+//!     .route("/", get(handler))
+//!     .layer(EnvironmentLayer::new(env));
+//! ```
+use crate::context::ContextMarker;
+use crate::environment::Environment;
+use crate::types::EnvType;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::middleware::AddExtension;
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use std::sync::Arc;
+use tower_layer::Layer;
+
+/// EnvironmentExtension is the shared environment inserted into the request extensions by EnvironmentLayer,
+/// with the status to respond when a context value is missing.
+#[derive(Clone)]
+pub struct EnvironmentExtension {
+    env: Arc<Environment>,
+    missing_status: StatusCode,
+}
+
+impl EnvironmentExtension {
+    /// Get the shared environment.
+    pub fn environment(&self) -> &Arc<Environment> {
+        &self.env
+    }
+}
+
+impl AsRef<Environment> for EnvironmentExtension {
+    fn as_ref(&self) -> &Environment {
+        &self.env
+    }
+}
+
+/// EnvironmentLayer is a layer to inject the shared environment into the request extensions.
+/// EnvValue responds with 500 Internal Server Error by default if the context value is missing,
+/// which can be changed with missing_status.
+#[derive(Clone)]
+pub struct EnvironmentLayer {
+    extension: EnvironmentExtension,
+}
+
+impl EnvironmentLayer {
+    /// Create a layer with the environment, or the environment already shared by Arc.
+    pub fn new<E: Into<Arc<Environment>>>(env: E) -> Self {
+        Self {
+            extension: EnvironmentExtension {
+                env: env.into(),
+                missing_status: StatusCode::INTERNAL_SERVER_ERROR,
+            },
+        }
+    }
+
+    /// Set the status to respond when a context value is missing.
+    pub fn missing_status(mut self, status: StatusCode) -> Self {
+        self.extension.missing_status = status;
+        self
+    }
+}
+
+impl<S> Layer<S> for EnvironmentLayer {
+    type Service = AddExtension<S, EnvironmentExtension>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Extension(self.extension.clone()).layer(inner)
+    }
+}
+
+/// EnvRejection is the rejection of the extractors, responded with the status and the message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvRejection {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl IntoResponse for EnvRejection {
+    fn into_response(self) -> Response {
+        (self.status, self.message).into_response()
+    }
+}
+
+/// Get the extension inserted by EnvironmentLayer, or reject with 500 Internal Server Error.
+fn extension(parts: &Parts) -> Result<&EnvironmentExtension, EnvRejection> {
+    parts
+        .extensions
+        .get::<EnvironmentExtension>()
+        .ok_or_else(|| EnvRejection {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: "environment is not found, add EnvironmentLayer to the router".to_string(),
+        })
+}
+
+/// CurrentEnv is an extractor of the current environment type.
+pub struct CurrentEnv(pub EnvType);
+
+impl<S: Send + Sync> FromRequestParts<S> for CurrentEnv {
+    type Rejection = EnvRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self(extension(parts)?.env.current_env()))
+    }
+}
+
+/// EnvValue is an extractor of the current value for the context marker.
+/// It is rejected with the status set by EnvironmentLayer::missing_status if the value is missing.
+pub struct EnvValue<M: ContextMarker>(pub M::Value);
+
+impl<M: ContextMarker, S: Send + Sync> FromRequestParts<S> for EnvValue<M> {
+    type Rejection = EnvRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let extension = extension(parts)?;
+        extension
+            .env
+            .current_value::<M>()
+            .map(Self)
+            .ok_or_else(|| EnvRejection {
+                status: extension.missing_status,
+                message: format!(
+                    "context value not found for {} in {:?}",
+                    std::any::type_name::<M>(),
+                    extension.env.current_env()
+                ),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::ContextBuilder;
+    use crate::environment::EnvironmentBuilder;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    struct UrlContext;
+
+    impl ContextMarker for UrlContext {
+        type Value = String;
+    }
+
+    async fn handler(CurrentEnv(env): CurrentEnv, EnvValue(url): EnvValue<UrlContext>) -> String {
+        format!("{env:?}: {url}")
+    }
+
+    fn app(current: EnvType) -> Router {
+        let env = EnvironmentBuilder::default()
+            .current_env(current)
+            .with_context(
+                ContextBuilder::<UrlContext>::default()
+                    .with_value(EnvType::Prod, "https://example.com".to_string())
+                    .build(),
+            )
+            .build()
+            .unwrap();
+        Router::new()
+            .route("/", get(handler))
+            .layer(EnvironmentLayer::new(env).missing_status(StatusCode::SERVICE_UNAVAILABLE))
+    }
+
+    async fn get_root(app: Router) -> (StatusCode, String) {
+        let response = app
+            .oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_present_value() {
+        let (status, body) = get_root(app(EnvType::Prod)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "Prod: https://example.com");
+    }
+
+    #[tokio::test]
+    async fn test_missing_value() {
+        let (status, body) = get_root(app(EnvType::Dev)).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body.contains("UrlContext"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_layer() {
+        let (status, _) = get_root(Router::new().route("/", get(handler))).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
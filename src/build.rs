@@ -0,0 +1,107 @@
+//! This is used to emit the environment type as a cfg from a build script.
+//! The build script reads the environment variable at build time,
+//! so the downstream crate can use `#[cfg(env_prod)]` and so on.
+//!
+//! # Example
+//!
+//! ```no_run
+//! // build.rs
+//! env_type::build::emit_env_cfg();
+//! ```
+//!
+//! ```ignore
+//! // src/main.rs
+//! #[cfg(env_prod)]
+//! const LOG_LEVEL: &str = "warn";
+//! #[cfg(not(env_prod))]
+//! const LOG_LEVEL: &str = "debug";
+//! ```
+use crate::types::{EnvKey, EnvType};
+
+/// Emit the cfg for the environment type from the default `ENV` variable.
+pub fn emit_env_cfg() {
+    emit_env_cfg_key::<EnvType>()
+}
+
+/// Emit the cfg for the environment type from the variable of the EnvKey.
+pub fn emit_env_cfg_key<K: EnvKey>() {
+    for line in env_cfg_lines(K::key(), &EnvType::from_env_key::<K>()) {
+        println!("{}", line);
+    }
+}
+
+/// Build the cargo instructions for the key and the environment type.
+/// The instructions are to rerun the build script when the variable changes,
+/// to declare the known cfg names, and to set the cfg of the environment type.
+///
+/// # Example
+///
+/// ```
+/// use env_type::build::env_cfg_lines;
+/// use env_type::types::EnvType;
+///
+/// let lines = env_cfg_lines("ENV", &EnvType::Prod);
+/// assert_eq!("cargo:rustc-cfg=env_prod", lines[2]);
+/// ```
+pub fn env_cfg_lines(key: &str, env: &EnvType) -> Vec<String> {
+    let mut names: Vec<_> = EnvType::VARIANTS.iter().map(cfg_name).collect();
+    let name = cfg_name(env);
+    if !names.contains(&name) {
+        names.push(name.clone());
+    }
+    vec![
+        format!("cargo:rerun-if-env-changed={}", key),
+        format!("cargo:rustc-check-cfg=cfg({})", names.join(", ")),
+        format!("cargo:rustc-cfg={}", name),
+    ]
+}
+
+/// The cfg name of the environment type, e.g. `env_prod`.
+/// Custom environment types are lowercased and non alphanumeric characters are replaced with `_`.
+fn cfg_name(env: &EnvType) -> String {
+    let name = match env {
+        EnvType::Dev => "dev",
+        EnvType::Test => "test",
+        EnvType::Stg => "stg",
+        EnvType::Prod => "prod",
+        EnvType::Local => "local",
+        EnvType::Ci => "ci",
+        EnvType::Custom(name) => name,
+    };
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("env_{}", name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_cfg_lines() {
+        assert_eq!(
+            env_cfg_lines("ENV", &EnvType::Prod),
+            vec![
+                "cargo:rerun-if-env-changed=ENV",
+                "cargo:rustc-check-cfg=cfg(env_dev, env_test, env_stg, env_prod, env_local, env_ci)",
+                "cargo:rustc-cfg=env_prod",
+            ]
+        );
+        assert_eq!(
+            env_cfg_lines("APP_ENV", &EnvType::Custom("pr-1234")),
+            vec![
+                "cargo:rerun-if-env-changed=APP_ENV",
+                "cargo:rustc-check-cfg=cfg(env_dev, env_test, env_stg, env_prod, env_local, env_ci, env_pr_1234)",
+                "cargo:rustc-cfg=env_pr_1234",
+            ]
+        );
+    }
+}
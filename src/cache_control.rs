@@ -0,0 +1,78 @@
+//! This is used to determine the default HTTP Cache-Control header for the current environment.
+//! Environment::cache_control is used to get the Cache-Control value from the environment.
+//!
+//! # Example
+//!
+//! ```
+//! use env_type::types::EnvType;
+//! use env_type::cache_control::cache_control_context;
+//! use env_type::environment::EnvironmentBuilder;
+//!
+//! let env = EnvironmentBuilder::default()
+//!  .current_env(EnvType::Prod)
+//!  .with_context(cache_control_context().build())
+//!  .build();
+//!
+//! assert!(env.is_ok());
+//! let env = env.unwrap();
+//! assert_eq!("public, max-age=3600", env.cache_control());
+//! ```
+use crate::context::{ContextBuilder, ContextMarker};
+use crate::environment::Environment;
+use crate::types::EnvType;
+
+/// The Cache-Control value used when no value is configured, nothing is cached.
+const NO_STORE: &str = "no-store";
+
+pub struct CacheControlContext;
+
+impl ContextMarker for CacheControlContext {
+    type Value = String;
+}
+
+/// The default cache control context.
+/// Prod is cached publicly for an hour, Stg is revalidated, and the others are not cached.
+pub fn cache_control_context() -> ContextBuilder<CacheControlContext> {
+    ContextBuilder::<CacheControlContext>::default()
+        .with_value(EnvType::Stg, "no-cache".to_string())
+        .with_value(EnvType::Prod, "public, max-age=3600".to_string())
+        .with_default(NO_STORE.to_string())
+}
+
+impl Environment {
+    /// Get the Cache-Control value for the current environment from the cache control context,
+    /// or "no-store" if the context is not registered.
+    pub fn cache_control(&self) -> String {
+        self.current_value::<CacheControlContext>()
+            .unwrap_or_else(|| NO_STORE.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::EnvironmentBuilder;
+
+    fn cache_control(env: EnvType) -> String {
+        EnvironmentBuilder::default()
+            .current_env(env)
+            .with_context(cache_control_context().build())
+            .build()
+            .unwrap()
+            .cache_control()
+    }
+
+    #[test]
+    fn test_cache_control() {
+        assert_eq!(cache_control(EnvType::Dev), "no-store");
+        assert_eq!(cache_control(EnvType::Test), "no-store");
+        assert_eq!(cache_control(EnvType::Stg), "no-cache");
+        assert_eq!(cache_control(EnvType::Prod), "public, max-age=3600");
+
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .build()
+            .unwrap();
+        assert_eq!(env.cache_control(), "no-store");
+    }
+}
@@ -0,0 +1,203 @@
+//! This is used to get the environment type from a `config::Config`, the merged view of files and env vars.
+//! The key is looked up as is, then lowercased and uppercased, because sources normalize the key casing differently.
+//! EnvTypeSource goes the other direction, and injects the environment type into the config tree.
+//!
+//! # Example
+//!
+//! ```
+//! use config::{Config, File, FileFormat};
+//! use env_type::config::EnvTypeSource;
+//! use env_type::types::EnvType;
+//!
+//! let config = Config::builder()
+//!     .add_source(File::from_str("env = \"prod\"", FileFormat::Toml))
+//!     .build()
+//!     .unwrap();
+//! let env = EnvType::from_config(&config).unwrap();
+//! assert_eq!(EnvType::Prod, env);
+//!
+//! let config = Config::builder()
+//!     .add_source(EnvTypeSource::new(env))
+//!     .build()
+//!     .unwrap();
+//! assert_eq!("production", config.get_string("env_type").unwrap());
+//! ```
+use crate::types::{AsEnvStr, AsEnvStrByKey, EnvError, EnvKey, EnvType};
+use ::config::{Config, ConfigError, Map, Source, Value};
+use std::str::FromStr;
+
+/// The default key of EnvTypeSource.
+const DEFAULT_SOURCE_KEY: &str = "env_type";
+
+/// Get the string at the key, or at the lowercased or the uppercased key.
+fn get_string(config: &Config, key: &str) -> Option<String> {
+    [
+        key.to_string(),
+        key.to_ascii_lowercase(),
+        key.to_ascii_uppercase(),
+    ]
+    .iter()
+    .find_map(|key| config.get_string(key).ok())
+}
+
+/// AsEnvStr is an implementation of the AsEnvStr trait.
+/// This implementation is used to get the environment type from the config.
+impl AsEnvStr for Config {
+    fn as_env_str<T: EnvKey>(&self) -> String {
+        self.as_env_str_by_key(T::key())
+    }
+}
+
+/// AsEnvStrByKey is an implementation of the AsEnvStrByKey trait.
+/// This is used to get the environment type from the config with a prefixed key.
+impl AsEnvStrByKey for Config {
+    fn as_env_str_by_key(&self, key: &str) -> String {
+        get_string(self, key).unwrap_or_default()
+    }
+}
+
+impl EnvType {
+    /// EnvType::from_config is a strict version of from_env_types for the config, with the key "ENV".
+    /// It returns EnvError::KeyNotFound if the key is not set,
+    /// and EnvError::InvalidEnvValue if the value is not a recognized environment type.
+    pub fn from_config(config: &Config) -> Result<Self, EnvError> {
+        Self::from_config_key::<Self>(config)
+    }
+
+    /// EnvType::from_config_key is a version of from_config with the key of K.
+    pub fn from_config_key<K: EnvKey>(config: &Config) -> Result<Self, EnvError> {
+        let key = K::key();
+        let value = get_string(config, key).ok_or_else(|| EnvError::KeyNotFound {
+            path: "config".to_string(),
+            key: key.to_string(),
+        })?;
+        Self::from_str(&value).map_err(|_| EnvError::InvalidEnvValue {
+            key: key.to_string(),
+            value,
+        })
+    }
+}
+
+/// EnvTypeSource is a config source that sets the canonical name of the environment type,
+/// at the key "env_type" by default, so the other sources and the application can read it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvTypeSource {
+    env: EnvType,
+    key: String,
+}
+
+impl EnvTypeSource {
+    /// Create the source of the environment type at the default key.
+    pub fn new(env: EnvType) -> Self {
+        Self {
+            env,
+            key: DEFAULT_SOURCE_KEY.to_string(),
+        }
+    }
+
+    /// Set the key of the environment type.
+    pub fn with_key(mut self, key: &str) -> Self {
+        self.key = key.to_string();
+        self
+    }
+}
+
+impl Source for EnvTypeSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        Ok(Map::from([(
+            self.key.clone(),
+            Value::from(self.env.canonical_str()),
+        )]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::config::{File, FileFormat};
+
+    fn config(toml: &str) -> Config {
+        Config::builder()
+            .add_source(File::from_str(toml, FileFormat::Toml))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_from_env_types() {
+        assert_eq!(
+            EnvType::from_env_types::<Config, EnvType>(config("ENV = \"stg\"")),
+            EnvType::Stg
+        );
+        assert_eq!(
+            EnvType::from_env_types::<Config, EnvType>(config("env = \"test\"")),
+            EnvType::Test
+        );
+        assert_eq!(
+            EnvType::from_env_types::<Config, EnvType>(config("name = \"app\"")),
+            EnvType::Dev
+        );
+    }
+
+    #[test]
+    fn test_from_config() {
+        assert_eq!(
+            EnvType::from_config(&config("env = \"p\"")),
+            Ok(EnvType::Prod)
+        );
+        assert_eq!(
+            EnvType::from_config(&config("name = \"app\"")),
+            Err(EnvError::KeyNotFound {
+                path: "config".to_string(),
+                key: "ENV".to_string()
+            })
+        );
+        assert_eq!(
+            EnvType::from_config(&config("ENV = \"banana\"")),
+            Err(EnvError::InvalidEnvValue {
+                key: "ENV".to_string(),
+                value: "banana".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_config_key() {
+        struct AppEnvKey;
+        impl EnvKey for AppEnvKey {
+            fn key() -> &'static str {
+                "app.env"
+            }
+        }
+
+        let config = config("[app]\nenv = \"ci\"");
+        assert_eq!(
+            EnvType::from_config_key::<AppEnvKey>(&config),
+            Ok(EnvType::Ci)
+        );
+    }
+
+    #[test]
+    fn test_source() {
+        let config = Config::builder()
+            .add_source(EnvTypeSource::new(EnvType::Prod))
+            .add_source(EnvTypeSource::new(EnvType::Stg).with_key("app.env"))
+            .build()
+            .unwrap();
+        assert_eq!(config.get_string("env_type").unwrap(), "production");
+        assert_eq!(config.get_string("app.env").unwrap(), "staging");
+        assert_eq!(
+            EnvType::from_env_types::<Config, EnvType>(
+                Config::builder()
+                    .add_source(EnvTypeSource::new(EnvType::Local).with_key("ENV"))
+                    .build()
+                    .unwrap()
+            ),
+            EnvType::Local
+        );
+    }
+}
@@ -0,0 +1,413 @@
+//! This is used to get the environment type and the context values from a TOML or JSON config file.
+//! The key of from_toml_path and from_json_path is a dotted path to a string value,
+//! so `app.environment` reads `environment` in the `[app]` table.
+//!
+//! The context values are read from a section per environment, e.g. `[dev]` and `[prod]`,
+//! and the `[default]` section is the default value of the context.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use env_type::config_file::from_toml_path;
+//! use env_type::context::{ContextBuilder, ContextMarker};
+//! use env_type::types::EnvType;
+//!
+//! struct PortContext;
+//!
+//! impl ContextMarker for PortContext {
+//!     type Value = u16;
+//! }
+//!
+//! // app.toml: environment = "prod"
+//! let env = from_toml_path("app.toml", "environment").unwrap();
+//! assert_eq!(EnvType::Prod, env);
+//!
+//! // envs.toml: [dev] port = 3000, [default] port = 80
+//! let context = ContextBuilder::<PortContext>::from_file("envs.toml", "port")
+//!     .unwrap()
+//!     .build();
+//! assert_eq!(Some(3000), context.get_for_env(&EnvType::Dev));
+//! ```
+use crate::context::{ContextBuilder, ContextMarker};
+use crate::environment::EnvironmentBuilder;
+use crate::types::{EnvError, EnvType};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// The section name of the default values.
+const DEFAULT_SECTION: &str = "default";
+
+/// from_toml_path is a function that returns the environment type from the string at the key of the TOML file.
+/// If the file does not exist, EnvError::FileNotFound is returned,
+/// and if the key does not exist, EnvError::KeyNotFound is returned.
+pub fn from_toml_path<P: AsRef<Path>>(path: P, key: &str) -> Result<EnvType, EnvError> {
+    ConfigFile::load_toml(path)?.env_type(key)
+}
+
+/// from_json_path is a function that returns the environment type from the string at the key of the JSON file.
+/// If the file does not exist, EnvError::FileNotFound is returned,
+/// and if the key does not exist, EnvError::KeyNotFound is returned.
+pub fn from_json_path<P: AsRef<Path>>(path: P, key: &str) -> Result<EnvType, EnvError> {
+    ConfigFile::load_json(path)?.env_type(key)
+}
+
+/// ConfigFile is a parsed TOML or JSON config file.
+/// Load it once to read the values of several contexts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigFile {
+    path: PathBuf,
+    value: Value,
+}
+
+impl ConfigFile {
+    /// Load the config file, a `.json` file is parsed as JSON and the others as TOML.
+    /// If the file does not exist, EnvError::FileNotFound is returned.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, EnvError> {
+        let path = path.as_ref();
+        match path.extension() {
+            Some(extension) if extension.eq_ignore_ascii_case("json") => Self::load_json(path),
+            _ => Self::load_toml(path),
+        }
+    }
+
+    /// Load the TOML file.
+    pub fn load_toml<P: AsRef<Path>>(path: P) -> Result<Self, EnvError> {
+        let path = path.as_ref();
+        let value = toml::from_str(&read(path)?)
+            .map_err(|err| EnvError::InvalidConfig(format!("{}: {}", path.display(), err)))?;
+        Ok(Self::new(path, value))
+    }
+
+    /// Load the JSON file.
+    pub fn load_json<P: AsRef<Path>>(path: P) -> Result<Self, EnvError> {
+        let path = path.as_ref();
+        let value = serde_json::from_str(&read(path)?)
+            .map_err(|err| EnvError::InvalidConfig(format!("{}: {}", path.display(), err)))?;
+        Ok(Self::new(path, value))
+    }
+
+    fn new(path: &Path, value: Value) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            value,
+        }
+    }
+
+    /// Parse the string at the dotted key as the environment type.
+    pub fn env_type(&self, key: &str) -> Result<EnvType, EnvError> {
+        let found = key
+            .split('.')
+            .try_fold(&self.value, |value, part| value.get(part))
+            .ok_or_else(|| self.key_not_found(key))?;
+        match found {
+            Value::String(s) => EnvType::parse(s),
+            other => Err(EnvError::InvalidEnvValue {
+                key: key.to_string(),
+                value: other.to_string(),
+            }),
+        }
+    }
+
+    /// Read the values of the key in the environment sections into the context builder.
+    /// The sections are parsed as EnvType, and the `[default]` section is the default value.
+    /// A missing section is not set, so it falls through to the default value.
+    ///
+    /// An unknown section is EnvError::UnknownSection, a section without the key is EnvError::KeyNotFound,
+    /// and a value of the wrong type is EnvError::TypeMismatch, the keys are named like `prod.port`.
+    pub fn context_builder<M>(&self, key: &str) -> Result<ContextBuilder<M>, EnvError>
+    where
+        M: ContextMarker,
+        M::Value: DeserializeOwned,
+    {
+        let sections = self.value.as_object().ok_or_else(|| {
+            EnvError::InvalidConfig(format!("{}: not a table", self.path.display()))
+        })?;
+        let mut builder = ContextBuilder::<M>::default();
+        for (section, values) in sections {
+            let env = if section == DEFAULT_SECTION {
+                None
+            } else {
+                let env = EnvType::parse(section).map_err(|_| EnvError::UnknownSection {
+                    path: self.path.display().to_string(),
+                    section: section.clone(),
+                })?;
+                Some(env)
+            };
+            let section_key = format!("{section}.{key}");
+            let value = values
+                .get(key)
+                .ok_or_else(|| self.key_not_found(&section_key))?;
+            let value =
+                serde_json::from_value(value.clone()).map_err(|err| EnvError::TypeMismatch {
+                    key: section_key,
+                    message: err.to_string(),
+                })?;
+            builder = match env {
+                Some(env) => builder.with_value(env, value),
+                None => builder.with_default(value),
+            };
+        }
+        Ok(builder)
+    }
+
+    fn key_not_found(&self, key: &str) -> EnvError {
+        EnvError::KeyNotFound {
+            path: self.path.display().to_string(),
+            key: key.to_string(),
+        }
+    }
+}
+
+impl<M> ContextBuilder<M>
+where
+    M: ContextMarker,
+    M::Value: DeserializeOwned,
+{
+    /// Create the context builder from the values of the key in the environment sections of the config file.
+    /// See ConfigFile::context_builder for the sections and the errors.
+    pub fn from_file<P: AsRef<Path>>(path: P, key: &str) -> Result<Self, EnvError> {
+        ConfigFile::load(path)?.context_builder(key)
+    }
+}
+
+impl EnvironmentBuilder {
+    /// Load the config file once, and register the contexts read from it with the hook.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use env_type::context::ContextMarker;
+    /// use env_type::environment::EnvironmentBuilder;
+    ///
+    /// struct PortContext;
+    ///
+    /// impl ContextMarker for PortContext {
+    ///     type Value = u16;
+    /// }
+    ///
+    /// let env = EnvironmentBuilder::from_env()
+    ///     .with_contexts_from_file("envs.toml", |builder, file| {
+    ///         Ok(builder.with_context(file.context_builder::<PortContext>("port")?.build()))
+    ///     })
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn with_contexts_from_file<P, F>(self, path: P, register: F) -> Result<Self, EnvError>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(Self, &ConfigFile) -> Result<Self, EnvError>,
+    {
+        let file = ConfigFile::load(path)?;
+        register(self, &file)
+    }
+}
+
+/// Read the config file, a missing file is EnvError::FileNotFound.
+fn read(path: &Path) -> Result<String, EnvError> {
+    std::fs::read_to_string(path).map_err(|err| match err.kind() {
+        std::io::ErrorKind::NotFound => EnvError::FileNotFound {
+            path: path.display().to_string(),
+        },
+        _ => EnvError::ProviderError(format!("{}: {}", path.display(), err)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::EnvironmentBuilder;
+
+    struct PortContext;
+
+    impl ContextMarker for PortContext {
+        type Value = u16;
+    }
+
+    struct UrlContext;
+
+    impl ContextMarker for UrlContext {
+        type Value = String;
+    }
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "env-type-config-file-{}-{}",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_from_toml_path() {
+        let path = temp_file(
+            "app.toml",
+            "environment = \"prod\"\n\n[app]\nenvironment = \"staging\"\nport = 8080\n",
+        );
+        let root = from_toml_path(&path, "environment");
+        let nested = from_toml_path(&path, "app.environment");
+        let missing = from_toml_path(&path, "env");
+        let not_string = from_toml_path(&path, "app.port");
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(root, Ok(EnvType::Prod));
+        assert_eq!(nested, Ok(EnvType::Stg));
+        assert_eq!(
+            missing,
+            Err(EnvError::KeyNotFound {
+                path: path.display().to_string(),
+                key: "env".to_string()
+            })
+        );
+        assert_eq!(
+            not_string,
+            Err(EnvError::InvalidEnvValue {
+                key: "app.port".to_string(),
+                value: "8080".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_json_path() {
+        let path = temp_file(
+            "app.json",
+            r#"{"environment": "test", "app": {"environment": "banana"}}"#,
+        );
+        let root = from_json_path(&path, "environment");
+        let invalid = from_json_path(&path, "app.environment");
+        let missing = from_json_path(&path, "app.env");
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(root, Ok(EnvType::Test));
+        assert_eq!(
+            invalid,
+            Err(EnvError::ParseError {
+                value: "banana".to_string()
+            })
+        );
+        assert_eq!(
+            missing,
+            Err(EnvError::KeyNotFound {
+                path: path.display().to_string(),
+                key: "app.env".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let path = std::env::temp_dir().join("env-type-config-file-missing.toml");
+        assert_eq!(
+            from_toml_path(&path, "environment"),
+            Err(EnvError::FileNotFound {
+                path: path.display().to_string()
+            })
+        );
+        assert!(matches!(
+            from_json_path(&path, "environment"),
+            Err(EnvError::FileNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_malformed_file() {
+        let path = temp_file("malformed.toml", "environment = \n");
+        let result = from_toml_path(&path, "environment");
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(EnvError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_context_from_toml() {
+        let context = ContextBuilder::<PortContext>::from_file(fixture("envs.toml"), "port")
+            .unwrap()
+            .build();
+        assert_eq!(context.get_for_env(&EnvType::Dev), Some(3000));
+        assert_eq!(context.get_for_env(&EnvType::Prod), Some(443));
+        assert_eq!(context.get_for_env(&EnvType::Stg), Some(8080));
+        assert_eq!(context.get_for_env(&EnvType::Test), Some(80));
+        assert_eq!(context.default_value(), Some(&80));
+    }
+
+    #[test]
+    fn test_context_from_json() {
+        let context = ContextBuilder::<UrlContext>::from_file(fixture("envs.json"), "url")
+            .unwrap()
+            .build();
+        assert_eq!(
+            context.get_for_env(&EnvType::Dev),
+            Some("http://localhost".to_string())
+        );
+        assert_eq!(
+            context.get_for_env(&EnvType::Prod),
+            Some("https://example.com".to_string())
+        );
+        assert_eq!(
+            context.get_for_env(&EnvType::Stg),
+            Some("https://default.example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_context_errors() {
+        assert_eq!(
+            ContextBuilder::<UrlContext>::from_file(fixture("envs.toml"), "url").err(),
+            Some(EnvError::KeyNotFound {
+                path: fixture("envs.toml").display().to_string(),
+                key: "default.url".to_string()
+            })
+        );
+        assert!(matches!(
+            ContextBuilder::<PortContext>::from_file(fixture("envs.toml"), "name").err(),
+            Some(EnvError::TypeMismatch { key, .. }) if key == "default.name"
+        ));
+        assert_eq!(
+            ContextBuilder::<PortContext>::from_file(fixture("unknown_section.toml"), "port").err(),
+            Some(EnvError::UnknownSection {
+                path: fixture("unknown_section.toml").display().to_string(),
+                section: "banana".to_string()
+            })
+        );
+        assert!(matches!(
+            ContextBuilder::<PortContext>::from_file(fixture("missing.toml"), "port").err(),
+            Some(EnvError::FileNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_with_contexts_from_file() {
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .with_contexts_from_file(fixture("envs.json"), |builder, file| {
+                Ok(builder
+                    .with_context(file.context_builder::<PortContext>("port")?.build())
+                    .with_context(file.context_builder::<UrlContext>("url")?.build()))
+            })
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(env.current_value::<PortContext>(), Some(443));
+        assert_eq!(
+            env.current_value::<UrlContext>(),
+            Some("https://example.com".to_string())
+        );
+
+        let result = EnvironmentBuilder::default().with_contexts_from_file(
+            fixture("envs.json"),
+            |builder, file| {
+                Ok(builder.with_context(file.context_builder::<PortContext>("host")?.build()))
+            },
+        );
+        assert!(matches!(result, Err(EnvError::KeyNotFound { .. })));
+    }
+}
@@ -1,6 +1,8 @@
 use crate::types::{EnvError, EnvType};
 use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::sync::{Arc, OnceLock};
+use thiserror::Error;
 
 /// Context marker trait for type-safe context values
 /// The Value type must be Clone, Send, Sync, and 'static strictly.
@@ -8,18 +10,78 @@ pub trait ContextMarker: Send + Sync + 'static {
     type Value: Clone + Send + Sync + 'static;
 }
 
+/// Slot holds a context value, either constructed eagerly or deferred until the first lookup.
+/// A lazy slot is shared by Arc, so clones of a context share the computed value.
+#[derive(Clone)]
+enum Slot<V> {
+    Ready(V),
+    Lazy(Arc<LazyValue<V>>),
+}
+
+/// LazyValue is the deferred value and its constructor, the constructor runs at most once.
+struct LazyValue<V> {
+    value: OnceLock<V>,
+    init: Box<dyn Fn() -> V + Send + Sync>,
+}
+
+impl<V> Slot<V> {
+    fn lazy<F>(init: F) -> Self
+    where
+        F: Fn() -> V + Send + Sync + 'static,
+    {
+        Slot::Lazy(Arc::new(LazyValue {
+            value: OnceLock::new(),
+            init: Box::new(init),
+        }))
+    }
+
+    fn get(&self) -> &V {
+        match self {
+            Slot::Ready(value) => value,
+            Slot::Lazy(lazy) => lazy.value.get_or_init(|| (lazy.init)()),
+        }
+    }
+}
+
 /// Context is Generic context container
 /// The context is a key-value store for environment values.
-#[derive(Clone)]
 pub struct Context<M: ContextMarker> {
     /// Environment values and values for each environment
-    env_values: HashMap<EnvType, M::Value>,
-    /// Default value for the context, if no value is found for the environment
-    default: Option<M::Value>,
+    env_values: HashMap<EnvType, Slot<M::Value>>,
+    /// Default values for the context in priority order, the first one is used if no value is found for the environment
+    defaults: Vec<Slot<M::Value>>,
+    /// Fallback environment for each environment, tried before the default value
+    fallbacks: HashMap<EnvType, EnvType>,
     /// Marker for the context type
     _marker: PhantomData<M>,
 }
 
+/// Clone the context, the marker type does not need to be Clone.
+/// Lazy values are shared with the clone, so they are constructed at most once for both.
+impl<M: ContextMarker> Clone for Context<M> {
+    fn clone(&self) -> Self {
+        Self {
+            env_values: self.env_values.clone(),
+            defaults: self.defaults.clone(),
+            fallbacks: self.fallbacks.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// The environments whose values differ between two contexts, with the values of both sides.
+/// The environment is None for the entry of the default values.
+pub type ContextDiff<V> = Vec<(Option<EnvType>, Option<V>, Option<V>)>;
+
+/// The error of Context::try_map, with the environment whose value failed the conversion.
+/// The environment is None if the default value failed.
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("Failed to map the context value for {env:?}: {error}")]
+pub struct MapError<E> {
+    pub env: Option<EnvType>,
+    pub error: E,
+}
+
 /// Context implementation for ContextMarker
 ///
 /// # Example
@@ -37,12 +99,12 @@ pub struct Context<M: ContextMarker> {
 /// let mut context = Context::<TestContext>::default();
 /// assert!(context.get_for_env(&EnvType::Dev).is_none());
 /// ```
-
 impl<M: ContextMarker> Default for Context<M> {
     fn default() -> Self {
         Self {
             env_values: HashMap::new(),
-            default: None,
+            defaults: Vec::new(),
+            fallbacks: HashMap::new(),
             _marker: PhantomData,
         }
     }
@@ -52,16 +114,455 @@ impl<M: ContextMarker> Context<M> {
     /// Get the value for the current environment
     /// If no value is found, return the default value(optional)
     pub fn get_for_env(&self, env: &EnvType) -> Option<M::Value> {
-        self.env_values
-            .get(env)
-            .cloned()
-            .or_else(|| self.default.clone())
+        self.get_for_env_ref(env).cloned()
+    }
+
+    /// Get the copied value for the current environment, like get_for_env for Copy values.
+    /// This makes the lookup explicitly cheap, e.g. for the bool of IsDebugContext.
+    pub fn get_for_env_copied(&self, env: &EnvType) -> Option<M::Value>
+    where
+        M::Value: Copy,
+    {
+        self.get_for_env_ref(env).copied()
+    }
+
+    /// Get the reference to the value for the current environment without cloning
+    /// If no value is found, the fallback chain is walked, then return the reference to the default value(optional)
+    pub fn get_for_env_ref(&self, env: &EnvType) -> Option<&M::Value> {
+        let mut env = env;
+        // a chain visits each fallback at most once, so a cycle is bounded here
+        for _ in 0..=self.fallbacks.len() {
+            if let Some(slot) = self.env_values.get(env) {
+                return Some(slot.get());
+            }
+            match self.fallbacks.get(env) {
+                Some(fallback) => env = fallback,
+                None => break,
+            }
+        }
+        self.defaults.first().map(Slot::get)
+    }
+
+    /// Alias of get_for_env_ref, for the callers looking for the reference getter by this name.
+    pub fn get_ref_for_env(&self, env: &EnvType) -> Option<&M::Value> {
+        self.get_for_env_ref(env)
+    }
+
+    /// Get the value for the environment, trying the given fallback environments in order, then the default value.
+    /// The fallbacks set on the builder are not followed, only the given ones are tried.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::context::{ContextBuilder, ContextMarker};
+    /// use env_type::types::EnvType;
+    ///
+    /// struct UrlContext;
+    ///
+    /// impl ContextMarker for UrlContext {
+    ///  type Value = String;
+    /// }
+    ///
+    /// let context = ContextBuilder::<UrlContext>::default()
+    ///  .with_value(EnvType::Prod, "https://example.com".to_string())
+    ///  .build();
+    ///
+    /// assert_eq!(
+    ///     Some("https://example.com".to_string()),
+    ///     context.get_for_env_with_fallbacks(&EnvType::Stg, &[EnvType::Prod])
+    /// );
+    /// ```
+    pub fn get_for_env_with_fallbacks(
+        &self,
+        env: &EnvType,
+        fallbacks: &[EnvType],
+    ) -> Option<M::Value> {
+        std::iter::once(env)
+            .chain(fallbacks)
+            .find_map(|env| self.env_values.get(env))
+            .or(self.defaults.first())
+            .map(|slot| slot.get().clone())
     }
 
     /// Try to get the value for the current environment
-    /// If no value is found, return an error
+    /// If no value is found, return an error with the context type, the environment,
+    /// and the environments tried in the fallback chain
     pub fn try_get_for_env(&self, env: &EnvType) -> Result<M::Value, EnvError> {
-        self.get_for_env(env).ok_or(EnvError::ContextValueNotFound)
+        self.get_for_env(env)
+            .ok_or_else(|| EnvError::ContextValueNotFound {
+                context: std::any::type_name::<M>().to_string(),
+                env: *env,
+                tried: self.fallback_chain(env),
+            })
+    }
+
+    /// Compare the resolved values with the other context, and list the environments whose values differ.
+    /// The values are resolved with fallbacks and the default value,
+    /// so a different default value shows up for every environment that resolves to it.
+    /// All known environment types and the custom environment types with explicit values are compared,
+    /// in promotion order.
+    /// The default values are compared last, and listed with None as the environment if they differ,
+    /// including when only one side has a default value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::context::{ContextBuilder, ContextMarker};
+    /// use env_type::types::EnvType;
+    ///
+    /// struct UrlContext;
+    ///
+    /// impl ContextMarker for UrlContext {
+    ///  type Value = String;
+    /// }
+    ///
+    /// let base = ContextBuilder::<UrlContext>::default()
+    ///  .with_value_fn(|_| "https://example.com".to_string())
+    ///  .build();
+    /// let other = ContextBuilder::<UrlContext>::default()
+    ///  .with_value_fn(|_| "https://example.com".to_string())
+    ///  .with_value(EnvType::Stg, "https://stg.example.com".to_string())
+    ///  .build();
+    ///
+    /// assert_eq!(
+    ///     vec![(
+    ///         Some(EnvType::Stg),
+    ///         Some("https://example.com".to_string()),
+    ///         Some("https://stg.example.com".to_string())
+    ///     )],
+    ///     base.diff(&other)
+    /// );
+    /// ```
+    pub fn diff(&self, other: &Context<M>) -> ContextDiff<M::Value>
+    where
+        M::Value: PartialEq,
+    {
+        let mut envs: Vec<EnvType> = EnvType::VARIANTS
+            .iter()
+            .chain(self.env_values.keys())
+            .chain(other.env_values.keys())
+            .copied()
+            .collect();
+        envs.sort();
+        envs.dedup();
+        let default = (None, self.default_value(), other.default_value());
+        envs.into_iter()
+            .map(|env| {
+                (
+                    Some(env),
+                    self.get_for_env_ref(&env),
+                    other.get_for_env_ref(&env),
+                )
+            })
+            .chain(std::iter::once(default))
+            .filter(|(_, left, right)| left != right)
+            .map(|(env, left, right)| (env, left.cloned(), right.cloned()))
+            .collect()
+    }
+
+    /// The environment and its fallback environments in lookup order, without repetition.
+    fn fallback_chain(&self, env: &EnvType) -> Vec<EnvType> {
+        let mut chain = vec![*env];
+        let mut env = env;
+        while let Some(fallback) = self.fallbacks.get(env) {
+            if chain.contains(fallback) {
+                break;
+            }
+            chain.push(*fallback);
+            env = fallback;
+        }
+        chain
+    }
+
+    /// Convert the context back into a builder to modify it.
+    /// The environment values, the default value and the fallbacks are preserved.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::is_debug::debug_context;
+    /// use env_type::types::EnvType;
+    ///
+    /// let context = debug_context()
+    ///  .build()
+    ///  .into_builder()
+    ///  .with_value(EnvType::Test, true)
+    ///  .build();
+    ///
+    /// assert_eq!(Some(true), context.get_for_env(&EnvType::Dev));
+    /// assert_eq!(Some(true), context.get_for_env(&EnvType::Test));
+    /// assert_eq!(Some(false), context.get_for_env(&EnvType::Prod));
+    /// ```
+    pub fn into_builder(self) -> ContextBuilder<M> {
+        ContextBuilder {
+            env_values: self.env_values,
+            defaults: self.defaults,
+            fallbacks: self.fallbacks,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Merge the other context into this context, and return the merged context.
+    /// The values and fallbacks in the other context win.
+    /// The default values of the other context win if set, otherwise the default values of this context are kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::context::{ContextBuilder, ContextMarker};
+    /// use env_type::types::EnvType;
+    ///
+    /// struct EndpointContext;
+    ///
+    /// impl ContextMarker for EndpointContext {
+    ///  type Value = String;
+    /// }
+    ///
+    /// let base = ContextBuilder::<EndpointContext>::default()
+    ///  .with_value(EnvType::Prod, "https://api.example.com".to_string())
+    ///  .with_default("http://localhost".to_string())
+    ///  .build();
+    /// let overrides = ContextBuilder::<EndpointContext>::default()
+    ///  .with_value(EnvType::Stg, "https://stg.example.com".to_string())
+    ///  .build();
+    ///
+    /// let context = base.merged_with(overrides);
+    /// assert_eq!(Some("https://api.example.com".to_string()), context.get_for_env(&EnvType::Prod));
+    /// assert_eq!(Some("https://stg.example.com".to_string()), context.get_for_env(&EnvType::Stg));
+    /// assert_eq!(Some("http://localhost".to_string()), context.get_for_env(&EnvType::Dev));
+    /// ```
+    pub fn merged_with(self, other: Context<M>) -> Context<M> {
+        let mut merged = self;
+        merged.env_values.extend(other.env_values);
+        merged.fallbacks.extend(other.fallbacks);
+        if !other.defaults.is_empty() {
+            merged.defaults = other.defaults;
+        }
+        merged
+    }
+
+    /// Merge the other context into this context, the same as merged_with.
+    /// The other context takes precedence for the values and the default values,
+    /// and the default values of this context are kept only if the other context has none.
+    pub fn merge(self, other: Context<M>) -> Context<M> {
+        self.merged_with(other)
+    }
+
+    /// Transform the context into a context of another marker, by applying the function to every value and the default values.
+    /// The fallbacks are kept. Lazy values are constructed, so the mapped context holds only eager values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::context::{ContextBuilder, ContextMarker};
+    /// use env_type::types::EnvType;
+    ///
+    /// struct PortStrContext;
+    ///
+    /// impl ContextMarker for PortStrContext {
+    ///  type Value = String;
+    /// }
+    ///
+    /// struct PortContext;
+    ///
+    /// impl ContextMarker for PortContext {
+    ///  type Value = usize;
+    /// }
+    ///
+    /// let context = ContextBuilder::<PortStrContext>::default()
+    ///  .with_value(EnvType::Prod, "443".to_string())
+    ///  .build()
+    ///  .map::<PortContext, _>(|port| port.parse().unwrap());
+    ///
+    /// assert_eq!(Some(443), context.get_for_env(&EnvType::Prod));
+    /// ```
+    pub fn map<N, F>(self, f: F) -> Context<N>
+    where
+        N: ContextMarker,
+        F: Fn(M::Value) -> N::Value,
+    {
+        match self.try_map::<N, _, std::convert::Infallible>(|value| Ok(f(value))) {
+            Ok(context) => context,
+            Err(err) => match err.error {},
+        }
+    }
+
+    /// Borrowing version of map, the context is kept as it is.
+    /// The function is applied to every value and the default values by reference, and the fallbacks are kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::context::{ContextBuilder, ContextMarker};
+    /// use env_type::types::EnvType;
+    ///
+    /// struct PortStrContext;
+    ///
+    /// impl ContextMarker for PortStrContext {
+    ///  type Value = String;
+    /// }
+    ///
+    /// struct PortContext;
+    ///
+    /// impl ContextMarker for PortContext {
+    ///  type Value = u16;
+    /// }
+    ///
+    /// let context = ContextBuilder::<PortStrContext>::default()
+    ///  .with_value(EnvType::Prod, "443".to_string())
+    ///  .with_default("80".to_string())
+    ///  .build();
+    /// let ports = context.map_ref::<PortContext, _>(|port| port.parse().unwrap());
+    ///
+    /// assert_eq!(Some(443), ports.get_for_env(&EnvType::Prod));
+    /// assert_eq!(Some(80), ports.get_for_env(&EnvType::Dev));
+    /// assert_eq!(Some("443".to_string()), context.get_for_env(&EnvType::Prod));
+    /// ```
+    pub fn map_ref<N, F>(&self, f: F) -> Context<N>
+    where
+        N: ContextMarker,
+        F: Fn(&M::Value) -> N::Value,
+    {
+        Context {
+            env_values: self
+                .env_values
+                .iter()
+                .map(|(env, slot)| (*env, Slot::Ready(f(slot.get()))))
+                .collect(),
+            defaults: self
+                .defaults
+                .iter()
+                .map(|slot| Slot::Ready(f(slot.get())))
+                .collect(),
+            fallbacks: self.fallbacks.clone(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Fallible version of map, the first failure is returned with its environment.
+    /// The values are converted in promotion order, then the default values.
+    pub fn try_map<N, F, E>(self, f: F) -> Result<Context<N>, MapError<E>>
+    where
+        N: ContextMarker,
+        F: Fn(M::Value) -> Result<N::Value, E>,
+    {
+        let mut values: Vec<_> = self.env_values.into_iter().collect();
+        values.sort_by_key(|(env, _)| *env);
+        let env_values = values
+            .into_iter()
+            .map(|(env, slot)| {
+                f(slot.get().clone())
+                    .map(|value| (env, Slot::Ready(value)))
+                    .map_err(|error| MapError {
+                        env: Some(env),
+                        error,
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        let defaults = self
+            .defaults
+            .into_iter()
+            .map(|slot| f(slot.get().clone()).map(Slot::Ready))
+            .collect::<Result<_, _>>()
+            .map_err(|error| MapError { env: None, error })?;
+        Ok(Context {
+            env_values,
+            defaults,
+            fallbacks: self.fallbacks,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Create a builder from the context by cloning the values, the context is kept as it is.
+    pub fn to_builder(&self) -> ContextBuilder<M> {
+        ContextBuilder {
+            env_values: self.env_values.clone(),
+            defaults: self.defaults.clone(),
+            fallbacks: self.fallbacks.clone(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Iterate the environments with explicit values in promotion order.
+    /// The order is deterministic, the known environment types first, then the custom ones.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::is_debug::debug_context;
+    /// use env_type::types::EnvType;
+    ///
+    /// let context = debug_context().build();
+    /// let envs: Vec<_> = context.envs().collect();
+    /// assert_eq!(vec![EnvType::Dev, EnvType::Local], envs);
+    /// ```
+    pub fn envs(&self) -> impl Iterator<Item = EnvType> {
+        let mut envs: Vec<EnvType> = self.env_values.keys().copied().collect();
+        envs.sort();
+        envs.into_iter()
+    }
+
+    /// Iterate the explicit values in promotion order, lazy values are constructed.
+    pub fn iter(&self) -> impl Iterator<Item = (EnvType, &M::Value)> {
+        self.envs()
+            .filter_map(|env| self.env_values.get(&env).map(|slot| (env, slot.get())))
+    }
+
+    /// Check if the environment has an explicit value, fallbacks and the default value are not considered.
+    pub fn has_explicit(&self, env: &EnvType) -> bool {
+        self.env_values.contains_key(env)
+    }
+
+    /// Get the reference to the default value, if set.
+    pub fn default_value(&self) -> Option<&M::Value> {
+        self.defaults.first().map(Slot::get)
+    }
+
+    /// Check if a default value is set, without constructing a lazy default value.
+    pub fn has_default(&self) -> bool {
+        !self.defaults.is_empty()
+    }
+
+    /// Remove the default value in use and return it, the next pushed default value takes over.
+    pub fn take_default(&mut self) -> Option<M::Value> {
+        if self.defaults.is_empty() {
+            return None;
+        }
+        let slot = self.defaults.remove(0);
+        Some(slot.get().clone())
+    }
+
+    /// Set the value for the environment, and return the previous explicit value, if any.
+    /// This adjusts a built context in place, e.g. in tests, without into_builder.
+    pub fn set(&mut self, env: EnvType, value: M::Value) -> Option<M::Value> {
+        self.env_values
+            .insert(env, Slot::Ready(value))
+            .map(|slot| slot.get().clone())
+    }
+
+    /// Remove the explicit value for the environment and return it,
+    /// so the environment falls through to its fallback or the default value.
+    pub fn remove(&mut self, env: &EnvType) -> Option<M::Value> {
+        self.env_values.remove(env).map(|slot| slot.get().clone())
+    }
+
+    /// The number of environments with explicit values.
+    pub fn len(&self) -> usize {
+        self.env_values.len()
+    }
+
+    /// Check if no environment has an explicit value.
+    pub fn is_empty(&self) -> bool {
+        self.env_values.is_empty()
+    }
+
+    /// Export the environment values sorted in promotion order.
+    /// The default value is not included, and lazy values are constructed.
+    /// The order is stable, so this is useful for snapshots and golden tests.
+    pub fn to_sorted_vec(&self) -> Vec<(EnvType, M::Value)> {
+        self.iter()
+            .map(|(env, value)| (env, value.clone()))
+            .collect()
     }
 }
 
@@ -91,8 +592,9 @@ impl<M: ContextMarker> Context<M> {
 /// assert_eq!(context.get_for_env(&EnvType::Stg), Some("default".to_string()));
 /// ```
 pub struct ContextBuilder<M: ContextMarker> {
-    env_values: HashMap<EnvType, M::Value>,
-    default: Option<M::Value>,
+    env_values: HashMap<EnvType, Slot<M::Value>>,
+    defaults: Vec<Slot<M::Value>>,
+    fallbacks: HashMap<EnvType, EnvType>,
     _marker: PhantomData<M>,
 }
 
@@ -100,7 +602,8 @@ impl<M: ContextMarker> Default for ContextBuilder<M> {
     fn default() -> Self {
         Self {
             env_values: HashMap::new(),
-            default: None,
+            defaults: Vec::new(),
+            fallbacks: HashMap::new(),
             _marker: PhantomData,
         }
     }
@@ -110,7 +613,38 @@ impl<M: ContextMarker> Default for ContextBuilder<M> {
 /// Create a new ContextBuilder with the environment values and default value.
 impl<M: ContextMarker> ContextBuilder<M> {
     pub fn with_value(mut self, env: EnvType, value: M::Value) -> Self {
-        self.env_values.insert(env, value);
+        self.env_values.insert(env, Slot::Ready(value));
+        self
+    }
+
+    /// Set the value for the environment, constructed by the function on the first lookup.
+    /// The function runs at most once, and never runs if the environment is not looked up.
+    /// Clones of the context share the constructed value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::context::{ContextBuilder, ContextMarker};
+    /// use env_type::types::EnvType;
+    ///
+    /// struct ConfigContext;
+    ///
+    /// impl ContextMarker for ConfigContext {
+    ///  type Value = Vec<String>;
+    /// }
+    ///
+    /// let context = ContextBuilder::<ConfigContext>::default()
+    ///  .with_lazy_value(EnvType::Prod, || vec!["expensive".to_string()])
+    ///  .with_value(EnvType::Dev, vec![])
+    ///  .build();
+    ///
+    /// assert_eq!(Some(vec!["expensive".to_string()]), context.get_for_env(&EnvType::Prod));
+    /// ```
+    pub fn with_lazy_value<F>(mut self, env: EnvType, f: F) -> Self
+    where
+        F: Fn() -> M::Value + Send + Sync + 'static,
+    {
+        self.env_values.insert(env, Slot::lazy(f));
         self
     }
 
@@ -120,21 +654,1079 @@ impl<M: ContextMarker> ContextBuilder<M> {
         M::Value: Clone,
     {
         for env in envs {
-            self.env_values.insert(env, value.clone());
+            self.env_values.insert(env, Slot::Ready(value.clone()));
         }
         self
     }
 
+    /// Set the value for every known environment type (EnvType::VARIANTS).
+    /// Like the other setters, this overrides the values already set,
+    /// and later with_value calls override individual environments.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::context::{ContextBuilder, ContextMarker};
+    /// use env_type::types::EnvType;
+    ///
+    /// struct TimeoutContext;
+    ///
+    /// impl ContextMarker for TimeoutContext {
+    ///  type Value = u64;
+    /// }
+    ///
+    /// let context = ContextBuilder::<TimeoutContext>::default()
+    ///  .with_all_envs(30)
+    ///  .with_value(EnvType::Prod, 5)
+    ///  .build();
+    ///
+    /// assert_eq!(Some(30), context.get_for_env(&EnvType::Stg));
+    /// assert_eq!(Some(5), context.get_for_env(&EnvType::Prod));
+    /// ```
+    pub fn with_all_envs(self, value: M::Value) -> Self {
+        self.with_values(EnvType::VARIANTS.iter().copied(), value)
+    }
+
+    /// Set the value for every known environment type (EnvType::VARIANTS) except the given ones.
+    /// The values of the excluded environments are left untouched.
+    pub fn with_all_except<I>(self, envs: I, value: M::Value) -> Self
+    where
+        I: IntoIterator<Item = EnvType>,
+    {
+        let excluded: Vec<_> = envs.into_iter().collect();
+        self.with_values(
+            EnvType::VARIANTS
+                .iter()
+                .copied()
+                .filter(|env| !excluded.contains(env)),
+            value,
+        )
+    }
+
+    /// Set the values from the map, later values override the values already set.
+    pub fn with_values_map(mut self, map: HashMap<EnvType, M::Value>) -> Self {
+        self.env_values.extend(
+            map.into_iter()
+                .map(|(env, value)| (env, Slot::Ready(value))),
+        );
+        self
+    }
+
+    /// Set the value for every known environment type (EnvType::VARIANTS) from the function.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::context::{ContextBuilder, ContextMarker};
+    /// use env_type::types::EnvType;
+    ///
+    /// struct DatabaseContext;
+    ///
+    /// impl ContextMarker for DatabaseContext {
+    ///  type Value = String;
+    /// }
+    ///
+    /// let context = ContextBuilder::<DatabaseContext>::default()
+    ///  .with_value_fn(|env| format!("db_{}", env.canonical_str()))
+    ///  .build();
+    ///
+    /// assert_eq!(Some("db_staging".to_string()), context.get_for_env(&EnvType::Stg));
+    /// ```
+    pub fn with_value_fn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(EnvType) -> M::Value,
+    {
+        for env in EnvType::VARIANTS {
+            self.env_values.insert(*env, Slot::Ready(f(*env)));
+        }
+        self
+    }
+
+    /// Set the value for every known environment type from the function, the same as with_value_fn.
+    /// A later with_value still overrides the value for its environment type.
+    pub fn with_all<F>(self, f: F) -> Self
+    where
+        F: Fn(EnvType) -> M::Value,
+    {
+        self.with_value_fn(f)
+    }
+
+    /// Set the default value, replacing the default values already set.
     pub fn with_default(mut self, value: M::Value) -> Self {
-        self.default = Some(value);
+        self.defaults = vec![Slot::Ready(value)];
+        self
+    }
+
+    /// Push the default value after the default values already set, for layered sources.
+    /// The first pushed default value is used, the later ones take over when it is taken.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::context::{ContextBuilder, ContextMarker};
+    /// use env_type::types::EnvType;
+    ///
+    /// struct UrlContext;
+    ///
+    /// impl ContextMarker for UrlContext {
+    ///  type Value = String;
+    /// }
+    ///
+    /// let mut context = ContextBuilder::<UrlContext>::default()
+    ///  .push_default("https://override.example.com".to_string())
+    ///  .push_default("https://example.com".to_string())
+    ///  .build();
+    ///
+    /// assert_eq!(Some("https://override.example.com".to_string()), context.get_for_env(&EnvType::Dev));
+    /// context.take_default();
+    /// assert_eq!(Some("https://example.com".to_string()), context.get_for_env(&EnvType::Dev));
+    /// ```
+    pub fn push_default(mut self, value: M::Value) -> Self {
+        self.defaults.push(Slot::Ready(value));
+        self
+    }
+
+    /// Set the default value, constructed by the function on the first lookup that falls through to it.
+    /// This replaces the default values already set, like with_default.
+    pub fn with_lazy_default<F>(mut self, f: F) -> Self
+    where
+        F: Fn() -> M::Value + Send + Sync + 'static,
+    {
+        self.defaults = vec![Slot::lazy(f)];
+        self
+    }
+
+    /// Let the function override every value and default value set so far, None keeps the value.
+    /// The override is resolved lazily on the first lookup of each value, and the result is kept.
+    /// The fallbacks are not touched, so an environment without a value still follows them.
+    pub(crate) fn with_override<F>(mut self, f: F) -> Self
+    where
+        F: Fn() -> Option<M::Value> + Send + Sync + 'static,
+    {
+        let f = Arc::new(f);
+        let wrap = |slot: Slot<M::Value>| {
+            let f = Arc::clone(&f);
+            Slot::lazy(move || f().unwrap_or_else(|| slot.get().clone()))
+        };
+        self.env_values = self
+            .env_values
+            .into_iter()
+            .map(|(env, slot)| (env, wrap(slot)))
+            .collect();
+        self.defaults = self.defaults.into_iter().map(wrap).collect();
+        self
+    }
+
+    /// Set the fallback environment, which is tried when no value is found for the environment.
+    /// Fallbacks are followed transitively before the default value is used.
+    /// A cycle in the fallbacks is stopped at lookup time, and the default value is used.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::context::{ContextBuilder, ContextMarker};
+    /// use env_type::types::EnvType;
+    ///
+    /// struct UrlContext;
+    ///
+    /// impl ContextMarker for UrlContext {
+    ///  type Value = String;
+    /// }
+    ///
+    /// let context = ContextBuilder::<UrlContext>::default()
+    ///  .with_value(EnvType::Prod, "https://example.com".to_string())
+    ///  .with_fallback(EnvType::Stg, EnvType::Prod)
+    ///  .build();
+    ///
+    /// assert_eq!(Some("https://example.com".to_string()), context.get_for_env(&EnvType::Stg));
+    /// ```
+    pub fn with_fallback(mut self, env: EnvType, fallback: EnvType) -> Self {
+        self.fallbacks.insert(env, fallback);
+        self
+    }
+
+    /// Set the fallbacks along the chain, each environment falls back to the next one.
+    /// e.g. `[Local, Dev, Test]` makes Local fall back to Dev, and Dev fall back to Test.
+    pub fn with_fallback_chain<I>(mut self, chain: I) -> Self
+    where
+        I: IntoIterator<Item = EnvType>,
+    {
+        let chain: Vec<_> = chain.into_iter().collect();
+        for pair in chain.windows(2) {
+            self.fallbacks.insert(pair[0], pair[1]);
+        }
+        self
+    }
+
+    /// Populate the builder from the context, the values and fallbacks in the context override the ones already set.
+    /// The default values are overridden only if the context has them.
+    pub fn extend_from(mut self, context: &Context<M>) -> Self {
+        self.env_values.extend(
+            context
+                .env_values
+                .iter()
+                .map(|(env, slot)| (*env, slot.clone())),
+        );
+        self.fallbacks.extend(context.fallbacks.iter());
+        if !context.defaults.is_empty() {
+            self.defaults = context.defaults.clone();
+        }
         self
     }
 
     pub fn build(self) -> Context<M> {
         Context {
             env_values: self.env_values,
-            default: self.default,
+            defaults: self.defaults,
+            fallbacks: self.fallbacks,
             _marker: PhantomData,
         }
     }
+
+    /// Build the context, requiring every environment to have a value.
+    /// A value is either an explicit value for the environment, or the default value.
+    /// Fails with EnvError::MissingEnvValues listing the environments without a value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::context::{ContextBuilder, ContextMarker};
+    /// use env_type::types::{EnvError, EnvType};
+    ///
+    /// struct UrlContext;
+    ///
+    /// impl ContextMarker for UrlContext {
+    ///  type Value = String;
+    /// }
+    ///
+    /// let result = ContextBuilder::<UrlContext>::default()
+    ///  .with_value(EnvType::Prod, "https://example.com".to_string())
+    ///  .with_default("http://localhost".to_string())
+    ///  .try_build();
+    /// assert!(result.is_ok());
+    ///
+    /// let result = ContextBuilder::<UrlContext>::default()
+    ///  .with_value(EnvType::Prod, "https://example.com".to_string())
+    ///  .try_build();
+    /// assert!(matches!(result, Err(EnvError::MissingEnvValues(_))));
+    /// ```
+    pub fn try_build(self) -> Result<Context<M>, EnvError> {
+        if !self.defaults.is_empty() {
+            return Ok(self.build());
+        }
+        self.try_build_complete()
+    }
+
+    /// Build the context, requiring an explicit value for every environment.
+    /// The default value is ignored.
+    /// Fails with EnvError::MissingEnvValues listing the environments without an explicit value.
+    pub fn try_build_complete(self) -> Result<Context<M>, EnvError> {
+        let missing: Vec<EnvType> = EnvType::VARIANTS
+            .iter()
+            .filter(|env| !self.env_values.contains_key(env))
+            .copied()
+            .collect();
+        if !missing.is_empty() {
+            return Err(EnvError::MissingEnvValues(missing));
+        }
+        Ok(self.build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestContext;
+
+    impl ContextMarker for TestContext {
+        type Value = String;
+    }
+
+    struct ListContext;
+
+    impl ContextMarker for ListContext {
+        type Value = Vec<String>;
+    }
+
+    #[test]
+    fn test_get_for_env_ref() {
+        let context = ContextBuilder::<ListContext>::default()
+            .with_value(EnvType::Dev, vec!["dev".to_string()])
+            .with_default(vec!["default".to_string()])
+            .build();
+
+        let dev = context.get_for_env_ref(&EnvType::Dev).unwrap();
+        assert_eq!(dev, &vec!["dev".to_string()]);
+        assert_eq!(
+            dev.as_ptr(),
+            context.get_for_env_ref(&EnvType::Dev).unwrap().as_ptr()
+        );
+
+        let prod = context.get_for_env_ref(&EnvType::Prod).unwrap();
+        assert_eq!(prod, &vec!["default".to_string()]);
+        assert_eq!(
+            prod.as_ptr(),
+            context.get_for_env_ref(&EnvType::Stg).unwrap().as_ptr()
+        );
+        // the cloning getter allocates a new value
+        let cloned = context.get_for_env(&EnvType::Prod).unwrap();
+        assert_ne!(prod.as_ptr(), cloned.as_ptr());
+
+        // the alias borrows the same value
+        assert_eq!(
+            dev.as_ptr(),
+            context.get_ref_for_env(&EnvType::Dev).unwrap().as_ptr()
+        );
+    }
+
+    #[test]
+    fn test_get_for_env_copied() {
+        use crate::is_debug::{debug_context, IsDebugContext};
+
+        let context: Context<IsDebugContext> = debug_context().build();
+        for env in EnvType::VARIANTS {
+            assert_eq!(
+                context.get_for_env_copied(env),
+                context.get_for_env(env),
+                "{:?}",
+                env
+            );
+        }
+        assert_eq!(context.get_for_env_copied(&EnvType::Dev), Some(true));
+        assert_eq!(context.get_for_env_copied(&EnvType::Prod), Some(false));
+        assert_eq!(
+            ContextBuilder::<IsDebugContext>::default()
+                .build()
+                .get_for_env_copied(&EnvType::Dev),
+            None
+        );
+    }
+
+    #[test]
+    fn test_custom_env() {
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Dev, "dev".to_string())
+            .with_value(EnvType::Custom("demo"), "demo".to_string())
+            .build();
+        assert_eq!(
+            context.get_for_env(&EnvType::Custom("demo")),
+            Some("demo".to_string())
+        );
+        // unknown environments fall through to the default
+        assert_eq!(context.get_for_env(&EnvType::Custom("pr-1234")), None);
+
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Dev, "dev".to_string())
+            .with_default("default".to_string())
+            .build();
+        assert_eq!(
+            context.get_for_env(&EnvType::Custom("pr-1234")),
+            Some("default".to_string())
+        );
+    }
+
+    #[test]
+    fn test_fallback() {
+        // multi-hop fallback
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Prod, "prod".to_string())
+            .with_value(EnvType::Dev, "dev".to_string())
+            .with_fallback_chain([EnvType::Test, EnvType::Stg, EnvType::Prod])
+            .with_fallback(EnvType::Local, EnvType::Dev)
+            .build();
+        assert_eq!(
+            context.get_for_env(&EnvType::Test),
+            Some("prod".to_string())
+        );
+        assert_eq!(context.get_for_env(&EnvType::Stg), Some("prod".to_string()));
+        assert_eq!(
+            context.get_for_env(&EnvType::Local),
+            Some("dev".to_string())
+        );
+        assert_eq!(context.get_for_env(&EnvType::Ci), None);
+        assert_eq!(
+            context.try_get_for_env(&EnvType::Ci),
+            Err(EnvError::ContextValueNotFound {
+                context: std::any::type_name::<TestContext>().to_string(),
+                env: EnvType::Ci,
+                tried: vec![EnvType::Ci]
+            })
+        );
+
+        // chain, then default
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Dev, "dev".to_string())
+            .with_fallback_chain([EnvType::Test, EnvType::Stg, EnvType::Prod])
+            .with_default("default".to_string())
+            .build();
+        assert_eq!(
+            context.get_for_env(&EnvType::Test),
+            Some("default".to_string())
+        );
+
+        // cycle is bounded at lookup time
+        let context = ContextBuilder::<TestContext>::default()
+            .with_fallback_chain([EnvType::Test, EnvType::Stg, EnvType::Prod, EnvType::Test])
+            .build();
+        assert_eq!(context.get_for_env(&EnvType::Stg), None);
+        assert_eq!(
+            context.try_get_for_env(&EnvType::Stg),
+            Err(EnvError::ContextValueNotFound {
+                context: std::any::type_name::<TestContext>().to_string(),
+                env: EnvType::Stg,
+                tried: vec![EnvType::Stg, EnvType::Prod, EnvType::Test]
+            })
+        );
+        let context = ContextBuilder::<TestContext>::default()
+            .with_fallback(EnvType::Stg, EnvType::Stg)
+            .with_default("default".to_string())
+            .build();
+        assert_eq!(
+            context.get_for_env(&EnvType::Stg),
+            Some("default".to_string())
+        );
+    }
+
+    #[test]
+    fn test_with_values_map() {
+        let map = HashMap::from([
+            (EnvType::Dev, "map_dev".to_string()),
+            (EnvType::Prod, "map_prod".to_string()),
+        ]);
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Dev, "dev".to_string())
+            .with_value(EnvType::Test, "test".to_string())
+            .with_values_map(map)
+            .build();
+        assert_eq!(
+            context.get_for_env(&EnvType::Dev),
+            Some("map_dev".to_string())
+        );
+        assert_eq!(
+            context.get_for_env(&EnvType::Test),
+            Some("test".to_string())
+        );
+        assert_eq!(
+            context.get_for_env(&EnvType::Prod),
+            Some("map_prod".to_string())
+        );
+    }
+
+    #[test]
+    fn test_with_value_fn() {
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Prod, "prod".to_string())
+            .with_value_fn(|env| format!("{:?}", env))
+            .with_value(EnvType::Test, "test".to_string())
+            .build();
+        assert_eq!(context.get_for_env(&EnvType::Dev), Some("Dev".to_string()));
+        assert_eq!(
+            context.get_for_env(&EnvType::Prod),
+            Some("Prod".to_string())
+        );
+        assert_eq!(
+            context.get_for_env(&EnvType::Test),
+            Some("test".to_string())
+        );
+        assert_eq!(context.to_sorted_vec().len(), EnvType::VARIANTS.len());
+    }
+
+    #[test]
+    fn test_with_all() {
+        let context = ContextBuilder::<TestContext>::default()
+            .with_all(|env| format!("db_{}", env.canonical_str()))
+            .with_value(EnvType::Prod, "primary".to_string())
+            .build();
+        for env in EnvType::VARIANTS {
+            let expected = match env {
+                EnvType::Prod => "primary".to_string(),
+                _ => format!("db_{}", env.canonical_str()),
+            };
+            assert_eq!(context.get_for_env(env), Some(expected));
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let base = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Dev, "dev".to_string())
+            .with_value(EnvType::Prod, "prod".to_string())
+            .with_default("default".to_string())
+            .build();
+        let other = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Dev, "dev".to_string())
+            .with_value(EnvType::Prod, "prod2".to_string())
+            .with_value(EnvType::Local, "default".to_string())
+            .with_default("default2".to_string())
+            .build();
+
+        assert!(base.diff(&base).is_empty());
+        assert_eq!(
+            base.diff(&other),
+            vec![
+                (
+                    Some(EnvType::Test),
+                    Some("default".to_string()),
+                    Some("default2".to_string())
+                ),
+                (
+                    Some(EnvType::Stg),
+                    Some("default".to_string()),
+                    Some("default2".to_string())
+                ),
+                (
+                    Some(EnvType::Prod),
+                    Some("prod".to_string()),
+                    Some("prod2".to_string())
+                ),
+                (
+                    Some(EnvType::Ci),
+                    Some("default".to_string()),
+                    Some("default2".to_string())
+                ),
+                (
+                    None,
+                    Some("default".to_string()),
+                    Some("default2".to_string())
+                ),
+            ]
+        );
+
+        let empty = Context::<TestContext>::default();
+        assert_eq!(
+            empty.diff(&base)[0],
+            (Some(EnvType::Dev), None, Some("dev".to_string()))
+        );
+        assert_eq!(
+            empty.diff(&base).last(),
+            Some(&(None, None, Some("default".to_string())))
+        );
+
+        // a default-only difference, every environment has the same explicit value
+        let without_default = ContextBuilder::<TestContext>::default()
+            .with_all_envs("all".to_string())
+            .build();
+        let with_default = without_default
+            .to_builder()
+            .with_default("default".to_string())
+            .build();
+        assert_eq!(
+            without_default.diff(&with_default),
+            vec![(None, None, Some("default".to_string()))]
+        );
+        assert_eq!(
+            with_default.diff(&without_default),
+            vec![(None, Some("default".to_string()), None)]
+        );
+        let other_default = with_default
+            .to_builder()
+            .with_default("default2".to_string())
+            .build();
+        assert_eq!(
+            with_default.diff(&other_default),
+            vec![(
+                None,
+                Some("default".to_string()),
+                Some("default2".to_string())
+            )]
+        );
+    }
+
+    #[test]
+    fn test_with_all_envs() {
+        use strum::VariantArray;
+
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Dev, "dev".to_string())
+            .with_all_envs("all".to_string())
+            .with_value(EnvType::Prod, "prod".to_string())
+            .build();
+        for env in <EnvType as VariantArray>::VARIANTS {
+            let expected = if env.is_prod() { "prod" } else { "all" };
+            assert_eq!(context.get_for_env(env), Some(expected.to_string()));
+        }
+        assert_eq!(context.get_for_env(&EnvType::Custom("demo")), None);
+
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Prod, "prod".to_string())
+            .with_all_except([EnvType::Prod, EnvType::Stg], "other".to_string())
+            .build();
+        for env in <EnvType as VariantArray>::VARIANTS {
+            let expected = match env {
+                EnvType::Prod => Some("prod".to_string()),
+                EnvType::Stg => None,
+                _ => Some("other".to_string()),
+            };
+            assert_eq!(context.get_for_env(env), expected);
+        }
+    }
+
+    #[test]
+    fn test_into_builder() {
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Dev, "dev".to_string())
+            .with_value(EnvType::Prod, "prod".to_string())
+            .with_fallback(EnvType::Stg, EnvType::Prod)
+            .with_default("default".to_string())
+            .build();
+
+        let copied = context.to_builder().build();
+        assert!(context.diff(&copied).is_empty());
+
+        let context = context
+            .into_builder()
+            .with_value(EnvType::Prod, "prod2".to_string())
+            .build();
+        assert_eq!(context.get_for_env(&EnvType::Dev), Some("dev".to_string()));
+        assert_eq!(
+            context.get_for_env(&EnvType::Prod),
+            Some("prod2".to_string())
+        );
+        assert_eq!(
+            context.get_for_env(&EnvType::Stg),
+            Some("prod2".to_string())
+        );
+        assert_eq!(
+            context.get_for_env(&EnvType::Test),
+            Some("default".to_string())
+        );
+        assert_eq!(copied.get_for_env(&EnvType::Prod), Some("prod".to_string()));
+    }
+
+    #[test]
+    fn test_to_sorted_vec() {
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Prod, "prod".to_string())
+            .with_value(EnvType::Dev, "dev".to_string())
+            .with_value(EnvType::Stg, "stg".to_string())
+            .with_value(EnvType::Test, "test".to_string())
+            .with_default("default".to_string())
+            .build();
+
+        assert_eq!(
+            context.to_sorted_vec(),
+            vec![
+                (EnvType::Dev, "dev".to_string()),
+                (EnvType::Test, "test".to_string()),
+                (EnvType::Stg, "stg".to_string()),
+                (EnvType::Prod, "prod".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_try_build() {
+        let complete = || {
+            ContextBuilder::<TestContext>::default()
+                .with_value_fn(|env| env.canonical_str().to_string())
+        };
+        assert!(complete().try_build().is_ok());
+        assert!(complete().try_build_complete().is_ok());
+
+        let partial = || {
+            ContextBuilder::<TestContext>::default()
+                .with_value(EnvType::Dev, "dev".to_string())
+                .with_value(EnvType::Prod, "prod".to_string())
+        };
+        let context = partial()
+            .with_default("default".to_string())
+            .try_build()
+            .unwrap();
+        assert_eq!(
+            context.get_for_env(&EnvType::Stg),
+            Some("default".to_string())
+        );
+        assert_eq!(
+            partial()
+                .with_default("default".to_string())
+                .try_build_complete()
+                .err(),
+            Some(EnvError::MissingEnvValues(vec![
+                EnvType::Test,
+                EnvType::Stg,
+                EnvType::Local,
+                EnvType::Ci,
+            ]))
+        );
+        assert_eq!(
+            partial().try_build().err(),
+            Some(EnvError::MissingEnvValues(vec![
+                EnvType::Test,
+                EnvType::Stg,
+                EnvType::Local,
+                EnvType::Ci,
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_lazy_value() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static PROD_CALLS: AtomicUsize = AtomicUsize::new(0);
+        static STG_CALLS: AtomicUsize = AtomicUsize::new(0);
+        static DEFAULT_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Dev, "dev".to_string())
+            .with_lazy_value(EnvType::Prod, || {
+                PROD_CALLS.fetch_add(1, Ordering::SeqCst);
+                "prod".to_string()
+            })
+            .with_lazy_value(EnvType::Stg, || {
+                STG_CALLS.fetch_add(1, Ordering::SeqCst);
+                "stg".to_string()
+            })
+            .with_lazy_default(|| {
+                DEFAULT_CALLS.fetch_add(1, Ordering::SeqCst);
+                "default".to_string()
+            })
+            .build();
+        assert_eq!(PROD_CALLS.load(Ordering::SeqCst), 0);
+
+        assert_eq!(context.get_for_env(&EnvType::Dev), Some("dev".to_string()));
+        assert_eq!(
+            context.get_for_env(&EnvType::Prod),
+            Some("prod".to_string())
+        );
+        assert_eq!(
+            context.get_for_env(&EnvType::Prod),
+            Some("prod".to_string())
+        );
+        let cloned = context.clone();
+        assert_eq!(cloned.get_for_env(&EnvType::Prod), Some("prod".to_string()));
+        assert_eq!(PROD_CALLS.load(Ordering::SeqCst), 1);
+
+        assert_eq!(
+            context.get_for_env(&EnvType::Test),
+            Some("default".to_string())
+        );
+        assert_eq!(
+            cloned.get_for_env(&EnvType::Ci),
+            Some("default".to_string())
+        );
+        assert_eq!(DEFAULT_CALLS.load(Ordering::SeqCst), 1);
+
+        assert_eq!(STG_CALLS.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_inspection() {
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Custom("preview"), "preview".to_string())
+            .with_value(EnvType::Prod, "prod".to_string())
+            .with_value(EnvType::Ci, "ci".to_string())
+            .with_value(EnvType::Dev, "dev".to_string())
+            .with_default("default".to_string())
+            .build();
+
+        assert_eq!(
+            context.envs().collect::<Vec<_>>(),
+            vec![
+                EnvType::Dev,
+                EnvType::Prod,
+                EnvType::Ci,
+                EnvType::Custom("preview")
+            ]
+        );
+        assert_eq!(
+            context
+                .iter()
+                .map(|(env, value)| (env, value.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (EnvType::Dev, "dev"),
+                (EnvType::Prod, "prod"),
+                (EnvType::Ci, "ci"),
+                (EnvType::Custom("preview"), "preview")
+            ]
+        );
+        assert!(context.has_explicit(&EnvType::Prod));
+        assert!(!context.has_explicit(&EnvType::Stg));
+        assert_eq!(context.default_value(), Some(&"default".to_string()));
+        assert_eq!(context.len(), 4);
+        assert!(!context.is_empty());
+
+        let context = Context::<TestContext>::default();
+        assert!(context.is_empty());
+        assert!(!context.has_default());
+        assert_eq!(context.default_value(), None);
+        assert_eq!(context.envs().next(), None);
+    }
+
+    #[test]
+    fn test_set_and_remove() {
+        let mut context = ContextBuilder::<TestContext>::default()
+            .with_lazy_value(EnvType::Stg, || "stg".to_string())
+            .with_default("default".to_string())
+            .build();
+
+        assert_eq!(context.set(EnvType::Prod, "prod".to_string()), None);
+        assert_eq!(
+            context.get_for_env(&EnvType::Prod),
+            Some("prod".to_string())
+        );
+        assert_eq!(
+            context.set(EnvType::Prod, "prod2".to_string()),
+            Some("prod".to_string())
+        );
+        assert_eq!(
+            context.get_for_env(&EnvType::Prod),
+            Some("prod2".to_string())
+        );
+
+        assert_eq!(context.remove(&EnvType::Prod), Some("prod2".to_string()));
+        assert_eq!(
+            context.get_for_env(&EnvType::Prod),
+            Some("default".to_string())
+        );
+        assert_eq!(context.remove(&EnvType::Prod), None);
+        assert_eq!(context.remove(&EnvType::Stg), Some("stg".to_string()));
+        assert!(context.is_empty());
+    }
+
+    #[test]
+    fn test_has_default() {
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Dev, "dev".to_string())
+            .with_value(EnvType::Prod, "prod".to_string())
+            .with_default("default".to_string())
+            .build();
+        assert_eq!(context.len(), 2);
+        let envs: Vec<_> = context.envs().collect();
+        assert!(envs.contains(&EnvType::Dev));
+        assert!(envs.contains(&EnvType::Prod));
+        assert!(context.has_default());
+
+        // a lazy default value is not constructed by the check
+        let context = ContextBuilder::<TestContext>::default()
+            .with_lazy_default(|| panic!("constructed"))
+            .build();
+        assert!(context.has_default());
+    }
+
+    #[test]
+    fn test_merged_with() {
+        let base = || {
+            ContextBuilder::<TestContext>::default()
+                .with_value(EnvType::Dev, "dev".to_string())
+                .with_value(EnvType::Prod, "prod".to_string())
+                .with_default("default".to_string())
+                .build()
+        };
+
+        // override one env, the base default is kept
+        let context = base().merged_with(
+            ContextBuilder::<TestContext>::default()
+                .with_value(EnvType::Prod, "override".to_string())
+                .build(),
+        );
+        assert_eq!(context.get_for_env(&EnvType::Dev), Some("dev".to_string()));
+        assert_eq!(
+            context.get_for_env(&EnvType::Prod),
+            Some("override".to_string())
+        );
+        assert_eq!(
+            context.get_for_env(&EnvType::Stg),
+            Some("default".to_string())
+        );
+
+        // override the default only
+        let context = base().merged_with(
+            ContextBuilder::<TestContext>::default()
+                .with_default("other".to_string())
+                .build(),
+        );
+        assert_eq!(
+            context.get_for_env(&EnvType::Prod),
+            Some("prod".to_string())
+        );
+        assert_eq!(
+            context.get_for_env(&EnvType::Stg),
+            Some("other".to_string())
+        );
+
+        // disjoint contexts
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Dev, "dev".to_string())
+            .build()
+            .merged_with(
+                ContextBuilder::<TestContext>::default()
+                    .with_value(EnvType::Test, "test".to_string())
+                    .build(),
+            );
+        assert_eq!(
+            context.to_sorted_vec(),
+            vec![
+                (EnvType::Dev, "dev".to_string()),
+                (EnvType::Test, "test".to_string())
+            ]
+        );
+        assert_eq!(context.default_value(), None);
+
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Stg, "stg".to_string())
+            .with_default("builder".to_string())
+            .extend_from(&base())
+            .build();
+        assert_eq!(context.get_for_env(&EnvType::Stg), Some("stg".to_string()));
+        assert_eq!(
+            context.get_for_env(&EnvType::Prod),
+            Some("prod".to_string())
+        );
+        assert_eq!(
+            context.get_for_env(&EnvType::Test),
+            Some("default".to_string())
+        );
+    }
+
+    #[test]
+    fn test_get_for_env_with_fallbacks() {
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Prod, "prod".to_string())
+            .with_value(EnvType::Stg, "stg".to_string())
+            .build();
+        assert_eq!(
+            context.get_for_env_with_fallbacks(&EnvType::Stg, &[EnvType::Prod]),
+            Some("stg".to_string())
+        );
+
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Prod, "prod".to_string())
+            .build();
+        assert_eq!(
+            context.get_for_env_with_fallbacks(&EnvType::Stg, &[EnvType::Test, EnvType::Prod]),
+            Some("prod".to_string())
+        );
+        assert_eq!(
+            context.get_for_env_with_fallbacks(&EnvType::Stg, &[EnvType::Test]),
+            None
+        );
+
+        let context = context
+            .into_builder()
+            .with_default("default".to_string())
+            .build();
+        assert_eq!(
+            context.get_for_env_with_fallbacks(&EnvType::Stg, &[EnvType::Test]),
+            Some("default".to_string())
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let base = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Dev, "a".to_string())
+            .with_default("x".to_string())
+            .build();
+        let context = base.merge(
+            ContextBuilder::<TestContext>::default()
+                .with_value(EnvType::Dev, "b".to_string())
+                .build(),
+        );
+        assert_eq!(context.get_for_env(&EnvType::Dev), Some("b".to_string()));
+        assert_eq!(context.get_for_env(&EnvType::Prod), Some("x".to_string()));
+    }
+
+    #[test]
+    fn test_map() {
+        struct SizeContext;
+        impl ContextMarker for SizeContext {
+            type Value = usize;
+        }
+
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Dev, "1".to_string())
+            .with_lazy_value(EnvType::Prod, || "3".to_string())
+            .with_default("2".to_string())
+            .with_fallback(EnvType::Stg, EnvType::Prod)
+            .build();
+        let mapped = context
+            .clone()
+            .map::<SizeContext, _>(|value| value.parse().unwrap());
+        assert_eq!(mapped.get_for_env(&EnvType::Dev), Some(1));
+        assert_eq!(mapped.get_for_env(&EnvType::Stg), Some(3));
+        assert_eq!(mapped.get_for_env(&EnvType::Test), Some(2));
+
+        let mapped = context.try_map::<SizeContext, _, _>(|value| value.parse::<usize>());
+        assert_eq!(mapped.unwrap().get_for_env(&EnvType::Prod), Some(3));
+
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Dev, "1".to_string())
+            .with_value(EnvType::Prod, "not a number".to_string())
+            .with_default("2".to_string())
+            .build();
+        let err = context
+            .try_map::<SizeContext, _, _>(|value| value.parse::<usize>())
+            .err()
+            .unwrap();
+        assert_eq!(err.env, Some(EnvType::Prod));
+        assert!(err.to_string().contains("Prod"));
+
+        let context = ContextBuilder::<TestContext>::default()
+            .with_default("not a number".to_string())
+            .build();
+        let err = context
+            .try_map::<SizeContext, _, _>(|value| value.parse::<usize>())
+            .err()
+            .unwrap();
+        assert_eq!(err.env, None);
+    }
+
+    #[test]
+    fn test_map_port() {
+        struct PortContext;
+        impl ContextMarker for PortContext {
+            type Value = u16;
+        }
+
+        let context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Prod, "8080".to_string())
+            .with_default("3000".to_string())
+            .build();
+        let ports = context.map_ref::<PortContext, _>(|port| port.parse().unwrap());
+        assert_eq!(ports.get_for_env(&EnvType::Prod), Some(8080));
+        assert_eq!(ports.get_for_env(&EnvType::Dev), Some(3000));
+        assert_eq!(ports.default_value(), Some(&3000));
+        assert_eq!(ports.envs().collect::<Vec<_>>(), vec![EnvType::Prod]);
+        assert_eq!(
+            context.get_for_env(&EnvType::Prod),
+            Some("8080".to_string())
+        );
+    }
+
+    #[test]
+    fn test_push_default() {
+        let mut context = ContextBuilder::<TestContext>::default()
+            .with_value(EnvType::Prod, "prod".to_string())
+            .push_default("first".to_string())
+            .push_default("second".to_string())
+            .build();
+        assert_eq!(
+            context.get_for_env(&EnvType::Prod),
+            Some("prod".to_string())
+        );
+        assert_eq!(
+            context.get_for_env(&EnvType::Dev),
+            Some("first".to_string())
+        );
+
+        assert_eq!(context.take_default(), Some("first".to_string()));
+        assert_eq!(
+            context.get_for_env(&EnvType::Dev),
+            Some("second".to_string())
+        );
+        assert_eq!(context.take_default(), Some("second".to_string()));
+        assert_eq!(context.get_for_env(&EnvType::Dev), None);
+        assert_eq!(context.take_default(), None);
+
+        // with_default replaces the pushed default values
+        let context = ContextBuilder::<TestContext>::default()
+            .push_default("first".to_string())
+            .with_default("default".to_string())
+            .push_default("second".to_string())
+            .build();
+        assert_eq!(context.default_value(), Some(&"default".to_string()));
+    }
 }
@@ -0,0 +1,48 @@
+//! This is used to load a `.env` file with dotenvy, before resolving the environment type with from_env.
+//! The variables already set in the process take precedence over the file.
+//! A missing file is not an error, because there is usually no `.env` file in production.
+//!
+//! # Example
+//!
+//! ```
+//! use env_type::types::EnvType;
+//!
+//! let env = env_type::dotenv::load_and_from_env();
+//! println!("{:?}", env);
+//! ```
+use crate::types::EnvType;
+use std::path::Path;
+
+/// load_and_from_env is a function that loads the `.env` file found in the current directory or its ancestors,
+/// and returns the environment type with EnvType::from_env.
+pub fn load_and_from_env() -> EnvType {
+    let _ = dotenvy::dotenv();
+    EnvType::from_env()
+}
+
+/// load_from_path is a function that loads the env file at the path,
+/// and returns the environment type with EnvType::from_env.
+pub fn load_from_path<P: AsRef<Path>>(path: P) -> EnvType {
+    let _ = dotenvy::from_path(path);
+    EnvType::from_env()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ScopedEnv;
+
+    #[test]
+    fn test_load_from_path() {
+        let mut guard = ScopedEnv::unset("ENV");
+
+        let path = std::env::temp_dir().join(format!("env-type-dotenv-{}.env", std::process::id()));
+        std::fs::write(&path, "ENV=staging\n").unwrap();
+        assert_eq!(load_from_path(&path), EnvType::Stg);
+        std::fs::remove_file(&path).unwrap();
+
+        // a missing file is not an error
+        guard.remove_var("ENV");
+        assert_eq!(load_from_path(&path), EnvType::Dev);
+    }
+}
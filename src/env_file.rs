@@ -0,0 +1,207 @@
+//! This is used to get the environment type and values from a `.env` file.
+//! The file is only read, the process environment is never modified.
+//!
+//! # Example
+//!
+//! ```
+//! use env_type::env_file::EnvFile;
+//! use env_type::types::EnvType;
+//!
+//! let file = EnvFile::parse("# local settings\nexport ENV=\"test\"\n").unwrap();
+//! let env = EnvType::from_env_types::<EnvFile, EnvType>(file);
+//! assert_eq!(EnvType::Test, env);
+//! ```
+use crate::types::{AsEnvStr, EnvError, EnvKey};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// The default file name searched by EnvFile::load_default.
+const DEFAULT_FILE_NAME: &str = ".env";
+
+/// EnvFile is a set of `KEY=VALUE` pairs read from a `.env` file.
+///
+/// The file format supports:
+/// - comments starting with `#` and blank lines
+/// - `export KEY=VALUE` prefixes
+/// - double quoted values with `\n`, `\t`, `\"` and `\\` escapes
+/// - single quoted values taken literally
+/// - inline comments after unquoted or quoted values
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EnvFile {
+    values: HashMap<String, String>,
+}
+
+impl EnvFile {
+    /// Load the `.env` file from the path.
+    /// If the file does not exist, EnvError::FileNotFound is returned.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, EnvError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => EnvError::FileNotFound {
+                path: path.display().to_string(),
+            },
+            _ => EnvError::ProviderError(format!("{}: {}", path.display(), err)),
+        })?;
+        Self::parse(&content).map_err(|err| match err {
+            EnvError::InvalidConfig(message) => {
+                EnvError::InvalidConfig(format!("{}: {}", path.display(), message))
+            }
+            err => err,
+        })
+    }
+
+    /// Load the `.env` file searched from the current directory to the parent directories.
+    /// If no file is found, EnvError::FileNotFound is returned.
+    pub fn load_default() -> Result<Self, EnvError> {
+        let current_dir =
+            std::env::current_dir().map_err(|err| EnvError::ProviderError(err.to_string()))?;
+        match Self::find(&current_dir) {
+            Some(path) => Self::load(path),
+            None => Err(EnvError::FileNotFound {
+                path: DEFAULT_FILE_NAME.to_string(),
+            }),
+        }
+    }
+
+    /// Find the `.env` file from the directory to the parent directories.
+    fn find(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|dir| dir.join(DEFAULT_FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    /// Parse the content of a `.env` file.
+    /// A line without `=` or with an empty key is an error with the line number.
+    pub fn parse(content: &str) -> Result<Self, EnvError> {
+        let mut values = HashMap::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| EnvError::InvalidConfig(format!("line {}: missing '='", i + 1)))?;
+            let key = key.trim();
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return Err(EnvError::InvalidConfig(format!(
+                    "line {}: invalid key '{}'",
+                    i + 1,
+                    key
+                )));
+            }
+            let value = Self::parse_value(value.trim()).ok_or_else(|| {
+                EnvError::InvalidConfig(format!("line {}: unclosed quote", i + 1))
+            })?;
+            values.insert(key.to_string(), value);
+        }
+        Ok(Self { values })
+    }
+
+    /// Parse a value, unquoting it if quoted.
+    /// None is returned if a quote is not closed.
+    fn parse_value(value: &str) -> Option<String> {
+        if let Some(rest) = value.strip_prefix('"') {
+            let mut parsed = String::new();
+            let mut chars = rest.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => return Some(parsed),
+                    '\\' => match chars.next()? {
+                        'n' => parsed.push('\n'),
+                        't' => parsed.push('\t'),
+                        other => parsed.push(other),
+                    },
+                    c => parsed.push(c),
+                }
+            }
+            None
+        } else if let Some(rest) = value.strip_prefix('\'') {
+            rest.find('\'').map(|end| rest[..end].to_string())
+        } else {
+            let value = match value.find(" #") {
+                Some(start) => &value[..start],
+                None => value,
+            };
+            Some(value.trim_end().to_string())
+        }
+    }
+
+    /// Get the value for the key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+}
+
+/// EnvFile is an implementation of the AsEnvStr trait.
+/// EnvFile is based on the values read from the file.
+impl AsEnvStr for EnvFile {
+    fn as_env_str<T: EnvKey>(&self) -> String {
+        self.get(T::key()).unwrap_or_default().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::EnvType;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn test_load() {
+        let file = EnvFile::load(fixture("valid.env")).unwrap();
+        assert_eq!(file.get("ENV"), Some("staging"));
+        assert_eq!(file.get("APP_ENV"), Some("prod"));
+        assert_eq!(file.get("RUST_ENV"), Some("test"));
+        assert_eq!(file.get("DATABASE_URL"), Some("postgres://localhost/app"));
+        assert_eq!(file.get("GREETING"), Some("hello\nworld"));
+        assert_eq!(file.get("EMPTY"), Some(""));
+        assert_eq!(file.get("MISSING"), None);
+
+        assert_eq!(
+            EnvType::from_env_types::<EnvFile, EnvType>(file),
+            EnvType::Stg
+        );
+    }
+
+    #[test]
+    fn test_load_malformed() {
+        assert_eq!(
+            EnvFile::load(fixture("malformed.env")),
+            Err(EnvError::InvalidConfig(format!(
+                "{}: line 2: missing '='",
+                fixture("malformed.env").display()
+            )))
+        );
+        assert!(EnvFile::parse("= value").is_err());
+        assert!(EnvFile::parse("KEY=\"unclosed").is_err());
+    }
+
+    #[test]
+    fn test_load_missing() {
+        assert_eq!(
+            EnvFile::load(fixture("missing.env")),
+            Err(EnvError::FileNotFound {
+                path: fixture("missing.env").display().to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_find() {
+        let root = std::env::temp_dir().join(format!("env-type-find-{}", std::process::id()));
+        let nested = root.join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(root.join(".env"), "ENV=prod\n").unwrap();
+
+        let found = EnvFile::find(&nested);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(found, Some(root.join(".env")));
+    }
+}
@@ -1,44 +1,698 @@
-use crate::context::{Context, ContextMarker};
-use crate::types::{EnvError, EnvType};
+use crate::context::{Context, ContextBuilder, ContextMarker};
+use crate::secret::SecretSource;
+use crate::types::{EnvError, EnvKey, EnvType};
 use std::any::{Any, TypeId};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Process-wide environment, set once by Environment::init_global.
+static GLOBAL_ENVIRONMENT: OnceLock<Environment> = OnceLock::new();
 
 /// Environment type that holds contexts, and the current environment.
 /// The current environment is the environment type.
 /// The contexts are the context type.
 /// The context type is a key-value pair of the environment type and the value.
 /// The value is the value for the environment type.
-#[derive(Clone, Debug)]
+/// The contexts are held behind Arc, so cloning the environment is cheap and shares the contexts.
 pub struct Environment {
     current: EnvType,
-    contexts: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+    /// Set while a CurrentEnvGuard from override_current is alive
+    current_override: CurrentOverride,
+    contexts: HashMap<TypeId, ContextEntry>,
+    /// Labels and renderers of the contexts for debug_snapshot, in registration order
+    renderers: Vec<RendererEntry>,
+}
+
+/// ContextEntry is a registered context with the type name of its context marker,
+/// and the type-erased describers for diagnostics.
+#[derive(Clone)]
+struct ContextEntry {
+    context: Arc<dyn Any + Send + Sync>,
+    type_name: &'static str,
+    describe: Describe,
+    /// Set only if the values can be formatted with Debug
+    describe_values: Option<Describe>,
+    /// Summarizes the context for Environment::to_json, with the current value if serializable
+    #[cfg(feature = "serde")]
+    to_json: ToJson,
+}
+
+/// Describe is a function that describes a type-erased context.
+type Describe = fn(&(dyn Any + Send + Sync)) -> String;
+
+impl ContextEntry {
+    fn new<M: ContextMarker>(context: Context<M>) -> Self {
+        Self {
+            context: Arc::new(context),
+            type_name: std::any::type_name::<M>(),
+            describe: describe_context::<M>,
+            describe_values: None,
+            #[cfg(feature = "serde")]
+            to_json: context_json::<M>,
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn with_serializable<M>(context: Context<M>) -> Self
+    where
+        M: ContextMarker,
+        M::Value: serde::Serialize,
+    {
+        Self {
+            to_json: context_json_with_value::<M>,
+            ..Self::new(context)
+        }
+    }
+
+    fn with_debug_values<M>(context: Context<M>) -> Self
+    where
+        M: ContextMarker,
+        M::Value: std::fmt::Debug,
+    {
+        Self {
+            describe_values: Some(describe_values::<M>),
+            ..Self::new(context)
+        }
+    }
+
+    /// Keep the value hooks of the replaced entry for the same context marker,
+    /// so a replaced context is still described and serialized with its values.
+    fn with_hooks_of(self, previous: &ContextEntry) -> Self {
+        Self {
+            describe_values: previous.describe_values,
+            #[cfg(feature = "serde")]
+            to_json: previous.to_json,
+            ..self
+        }
+    }
+}
+
+/// Describe the environments with explicit values and the existence of a default value, without the values.
+fn describe_context<M: ContextMarker>(context: &(dyn Any + Send + Sync)) -> String {
+    match context.downcast_ref::<Context<M>>() {
+        Some(context) => format!(
+            "explicit: {:?}, has_default: {}",
+            context.envs().collect::<Vec<_>>(),
+            context.has_default()
+        ),
+        None => String::new(),
+    }
+}
+
+/// Describe the explicit values and the default value with Debug, one per line.
+fn describe_values<M>(context: &(dyn Any + Send + Sync)) -> String
+where
+    M: ContextMarker,
+    M::Value: std::fmt::Debug,
+{
+    let Some(context) = context.downcast_ref::<Context<M>>() else {
+        return String::new();
+    };
+    let mut lines: Vec<String> = context
+        .iter()
+        .map(|(env, value)| format!("    {env:?} = {value:?}"))
+        .collect();
+    if let Some(value) = context.default_value() {
+        lines.push(format!("    default = {value:?}"));
+    }
+    lines.join("\n")
+}
+
+/// ToJson is a function that summarizes a type-erased context as JSON for the current environment.
+#[cfg(feature = "serde")]
+type ToJson = fn(&(dyn Any + Send + Sync), &EnvType) -> serde_json::Value;
+
+/// Summarize the environments with explicit values and the existence of a default value, without the values.
+#[cfg(feature = "serde")]
+fn context_json<M: ContextMarker>(
+    context: &(dyn Any + Send + Sync),
+    _current: &EnvType,
+) -> serde_json::Value {
+    match context.downcast_ref::<Context<M>>() {
+        Some(context) => serde_json::json!({
+            "has_default": context.has_default(),
+            "envs": context.envs().collect::<Vec<_>>(),
+        }),
+        None => serde_json::Value::Null,
+    }
+}
+
+/// Summarize the context like context_json, with the serialized value for the current environment.
+/// The value is null if no value is found, or if it fails to serialize.
+#[cfg(feature = "serde")]
+fn context_json_with_value<M>(
+    context: &(dyn Any + Send + Sync),
+    current: &EnvType,
+) -> serde_json::Value
+where
+    M: ContextMarker,
+    M::Value: serde::Serialize,
+{
+    let mut json = context_json::<M>(context, current);
+    let value = context
+        .downcast_ref::<Context<M>>()
+        .and_then(|context| context.get_for_env_ref(current))
+        .and_then(|value| serde_json::to_value(value).ok())
+        .unwrap_or(serde_json::Value::Null);
+    if let serde_json::Value::Object(map) = &mut json {
+        map.insert("current_value".to_string(), value);
+    }
+    json
+}
+
+/// A clone takes the current environment as seen at the time of the clone,
+/// and is not affected by the guards of the original.
+impl Clone for Environment {
+    fn clone(&self) -> Self {
+        Self {
+            current: self.current_env(),
+            current_override: CurrentOverride::default(),
+            contexts: self.contexts.clone(),
+            renderers: self.renderers.clone(),
+        }
+    }
+}
+
+/// Debug shows the current environment, the number and the type names of the registered contexts,
+/// e.g. for startup logs.
+impl std::fmt::Debug for Environment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Environment")
+            .field("current", &self.current_env())
+            .field("context_count", &self.context_count())
+            .field("contexts", &self.context_names())
+            .finish()
+    }
+}
+
+/// ValueCheck is a function that checks a required context of the built environment.
+type ValueCheck = fn(&Environment) -> Result<(), EnvError>;
+
+/// Check that the context resolves a value for the current environment, explicitly or by the default value.
+fn check_current_value<M: ContextMarker>(env: &Environment) -> Result<(), EnvError> {
+    env.try_current_value::<M>().map(|_| ())
+}
+
+/// PendingSecret registers a loaded secret for the current environment, once it is known in build.
+type PendingSecret = Box<dyn FnOnce(EnvironmentBuilder, EnvType) -> EnvironmentBuilder + Send>;
+
+/// Renderer is a function that renders the current value of a context for diagnostics.
+pub type Renderer = fn(&Environment) -> String;
+
+/// RendererEntry is a label and its renderer for debug_snapshot,
+/// with the context marker if it renders a context registered by with_rendered_context.
+#[derive(Clone)]
+struct RendererEntry {
+    type_id: Option<TypeId>,
+    label: &'static str,
+    render: Renderer,
+}
+
+/// Render the current value of the context with Debug, or "<unset>" if no value is found.
+fn render_current<M>(env: &Environment) -> String
+where
+    M: ContextMarker,
+    M::Value: std::fmt::Debug,
+{
+    match env.current_value::<M>() {
+        Some(value) => format!("{value:?}"),
+        None => "<unset>".to_string(),
+    }
+}
+
+/// CurrentOverride is the stack of the environments overriding the current one, with the ids of their guards.
+/// The depth is kept in an atomic, so current_env does not lock while nothing is overridden.
+#[derive(Default)]
+struct CurrentOverride {
+    depth: AtomicUsize,
+    next_id: AtomicU64,
+    stack: Mutex<Vec<(u64, EnvType)>>,
+}
+
+impl CurrentOverride {
+    /// Get the innermost overriding environment, if any.
+    fn get(&self) -> Option<EnvType> {
+        if self.depth.load(Ordering::Acquire) == 0 {
+            return None;
+        }
+        self.lock().last().map(|(_, env)| *env)
+    }
+
+    /// Push the overriding environment, and return the id to remove it with.
+    fn push(&self, env: EnvType) -> u64 {
+        let mut stack = self.lock();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        stack.push((id, env));
+        self.depth.store(stack.len(), Ordering::Release);
+        id
+    }
+
+    /// Remove the overriding environment of the id, wherever it is in the stack.
+    fn remove(&self, id: u64) {
+        let mut stack = self.lock();
+        stack.retain(|(other, _)| *other != id);
+        self.depth.store(stack.len(), Ordering::Release);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<(u64, EnvType)>> {
+        self.stack.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// CurrentEnvGuard is a guard returned by Environment::override_current.
+/// Dropping the guard removes its override, the innermost remaining override or the built environment is current then,
+/// so guards dropped out of order never leave a stale environment in place.
+#[must_use = "the override is reverted when the guard is dropped"]
+pub struct CurrentEnvGuard<'a> {
+    env: &'a Environment,
+    id: u64,
+}
+
+impl Drop for CurrentEnvGuard<'_> {
+    fn drop(&mut self) {
+        self.env.current_override.remove(self.id);
+    }
 }
 
 /// Environment struct implementation
 /// The Environment struct has the current environment and the contexts.
 impl Environment {
-    /// Get the current environment
-    pub fn current_env(&self) -> &EnvType {
-        &self.current
+    /// Get the current environment, or the overriding one while a guard of override_current is alive.
+    /// This does not lock while nothing is overridden.
+    pub fn current_env(&self) -> EnvType {
+        self.current_override.get().unwrap_or(self.current)
+    }
+
+    /// Check if the current environment is Dev.
+    pub fn is_dev(&self) -> bool {
+        self.current_env().is_dev()
+    }
+
+    /// Check if the current environment is Test.
+    pub fn is_test(&self) -> bool {
+        self.current_env().is_test()
+    }
+
+    /// Check if the current environment is Stg.
+    pub fn is_stg(&self) -> bool {
+        self.current_env().is_stg()
+    }
+
+    /// Check if the current environment is Prod.
+    pub fn is_prod(&self) -> bool {
+        self.current_env().is_prod()
+    }
+
+    /// Check if the current environment is Local.
+    pub fn is_local(&self) -> bool {
+        self.current_env().is_local()
+    }
+
+    /// Check if the current environment is Ci.
+    pub fn is_ci(&self) -> bool {
+        self.current_env().is_ci()
+    }
+
+    /// Override the current environment until the returned guard is dropped,
+    /// e.g. to act as Prod in a test or a feature preview without rebuilding the environment.
+    /// current_env, current_value and is_debug reflect the override.
+    /// The override is scoped to this Environment instance: the other threads sharing it see the override,
+    /// but clones and the other environments do not. Nested overrides are restored in LIFO order,
+    /// and a guard dropped out of order only removes its own override.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::environment::EnvironmentBuilder;
+    /// use env_type::is_debug::{debug_context, IsDebug};
+    /// use env_type::types::EnvType;
+    ///
+    /// let env = EnvironmentBuilder::default()
+    ///  .current_env(EnvType::Dev)
+    ///  .with_context(debug_context().build())
+    ///  .build()
+    ///  .unwrap();
+    ///
+    /// {
+    ///     let _guard = env.override_current(EnvType::Prod);
+    ///     assert_eq!(EnvType::Prod, env.current_env());
+    ///     assert!(!env.is_debug());
+    /// }
+    /// assert_eq!(EnvType::Dev, env.current_env());
+    /// assert!(env.is_debug());
+    /// ```
+    pub fn override_current(&self, env: EnvType) -> CurrentEnvGuard<'_> {
+        CurrentEnvGuard {
+            env: self,
+            id: self.current_override.push(env),
+        }
     }
 
     /// Get the context for the context marker
     pub fn context<M: ContextMarker>(&self) -> Option<&Context<M>> {
         self.contexts
             .get(&TypeId::of::<M>())
-            .and_then(|ctx| ctx.downcast_ref())
+            .and_then(|entry| entry.context.downcast_ref())
+    }
+
+    /// Check if the context for the context marker is registered.
+    pub fn has_context<M: ContextMarker>(&self) -> bool {
+        self.contexts.contains_key(&TypeId::of::<M>())
+    }
+
+    /// The number of the registered contexts.
+    pub fn context_count(&self) -> usize {
+        self.contexts.len()
+    }
+
+    /// The type names of the context markers of the registered contexts, sorted for diagnostics.
+    pub fn context_names(&self) -> Vec<&'static str> {
+        let mut names: Vec<_> = self
+            .contexts
+            .values()
+            .map(|entry| entry.type_name)
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Serialize the resolved configuration to JSON for diagnostics, e.g. to attach to a support ticket.
+    /// Every context is listed by the type name of its context marker, with the environments with explicit values
+    /// and the existence of a default value. The value for the current environment is included
+    /// only for the contexts registered with EnvironmentBuilder::with_context_serializable.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::environment::EnvironmentBuilder;
+    /// use env_type::is_debug::debug_context;
+    /// use env_type::types::EnvType;
+    ///
+    /// let env = EnvironmentBuilder::default()
+    ///  .current_env(EnvType::Prod)
+    ///  .with_context_serializable(debug_context().build())
+    ///  .build()
+    ///  .unwrap();
+    ///
+    /// let json = env.to_json();
+    /// assert_eq!("production", json["current"]);
+    /// assert_eq!(false, json["contexts"]["env_type::is_debug::IsDebugContext"]["current_value"]);
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> serde_json::Value {
+        let current = self.current_env();
+        let contexts: serde_json::Map<String, serde_json::Value> = self
+            .contexts
+            .values()
+            .map(|entry| {
+                (
+                    entry.type_name.to_string(),
+                    (entry.to_json)(entry.context.as_ref(), &current),
+                )
+            })
+            .collect();
+        serde_json::json!({
+            "current": current,
+            "contexts": contexts,
+        })
     }
 
     /// Get the current value for the context marker
     pub fn current_value<M: ContextMarker>(&self) -> Option<M::Value> {
-        self.value::<M>(self.current_env())
+        self.value::<M>(&self.current_env())
+    }
+
+    /// Try to get the current value for the context marker.
+    /// Returns EnvError::MissingRequiredContext if the context is not registered,
+    /// and EnvError::ContextValueNotFound if it has no value for the current environment.
+    pub fn try_current_value<M: ContextMarker>(&self) -> Result<M::Value, EnvError> {
+        match self.context::<M>() {
+            Some(context) => context.try_get_for_env(&self.current_env()),
+            None => Err(EnvError::MissingRequiredContext {
+                name: std::any::type_name::<M>(),
+            }),
+        }
+    }
+
+    /// Get the current value for the context marker, or the default if no value is found.
+    pub fn current_value_or<M: ContextMarker>(&self, default: M::Value) -> M::Value {
+        self.current_value::<M>().unwrap_or(default)
+    }
+
+    /// Get the current value for the context marker, or the value computed by the function if no value is found.
+    pub fn current_value_or_else<M, F>(&self, f: F) -> M::Value
+    where
+        M: ContextMarker,
+        F: FnOnce() -> M::Value,
+    {
+        self.current_value::<M>().unwrap_or_else(f)
     }
 
     /// Get the value for the context marker and the environment type
     pub fn value<M: ContextMarker>(&self, env: &EnvType) -> Option<M::Value> {
         self.context::<M>().and_then(|ctx| ctx.get_for_env(env))
     }
+
+    /// Get the reference to the current value for the context marker without cloning
+    pub fn current_value_ref<M: ContextMarker>(&self) -> Option<&M::Value> {
+        self.value_ref::<M>(&self.current_env())
+    }
+
+    /// Get the reference to the value for the context marker and the environment type without cloning
+    pub fn value_ref<M: ContextMarker>(&self, env: &EnvType) -> Option<&M::Value> {
+        self.context::<M>().and_then(|ctx| ctx.get_for_env_ref(env))
+    }
+
+    /// Set the current environment.
+    /// This is primarily for test ergonomics, to flip the environment without rebuilding it.
+    pub fn set_current(&mut self, env: EnvType) {
+        self.current = env;
+    }
+
+    /// Insert the context for the context marker, and return the replaced one if registered.
+    /// The contexts of the other context markers are kept.
+    /// This is useful for tests, and for plugins that register their contexts after the environment is built.
+    /// If the replaced context is shared with a clone of the environment, it is cloned.
+    /// The replaced context keeps showing its values in describe_with_values and to_json,
+    /// if it was registered with with_rendered_context or with_context_serializable.
+    pub fn insert_context<M: ContextMarker>(&mut self, context: Context<M>) -> Option<Context<M>> {
+        let type_id = TypeId::of::<M>();
+        let entry = match self.contexts.get(&type_id) {
+            Some(previous) => ContextEntry::new(context).with_hooks_of(previous),
+            None => ContextEntry::new(context),
+        };
+        self.contexts
+            .insert(type_id, entry)
+            .and_then(|previous| previous.context.downcast::<Context<M>>().ok())
+            .map(Arc::unwrap_or_clone)
+    }
+
+    /// Overlay the values on the string context for the context marker.
+    /// The values are merged into the existing context, or a new context is created with them.
+    /// This is primarily for test ergonomics, to set up a scenario tersely.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::context::ContextMarker;
+    /// use env_type::environment::EnvironmentBuilder;
+    /// use env_type::types::EnvType;
+    /// use std::collections::HashMap;
+    ///
+    /// struct UrlContext;
+    ///
+    /// impl ContextMarker for UrlContext {
+    ///  type Value = String;
+    /// }
+    ///
+    /// let mut env = EnvironmentBuilder::default()
+    ///  .current_env(EnvType::Test)
+    ///  .build()
+    ///  .unwrap();
+    /// env.overlay_string_context::<UrlContext>(HashMap::from([(
+    ///     EnvType::Test,
+    ///     "http://localhost:8080".to_string(),
+    /// )]));
+    ///
+    /// assert_eq!(Some("http://localhost:8080".to_string()), env.current_value::<UrlContext>());
+    /// ```
+    pub fn overlay_string_context<M>(&mut self, values: HashMap<EnvType, String>)
+    where
+        M: ContextMarker<Value = String>,
+    {
+        let overlay = ContextBuilder::<M>::default()
+            .with_values_map(values)
+            .build();
+        let context = match self.context::<M>() {
+            Some(context) => context.clone().merged_with(overlay),
+            None => overlay,
+        };
+        self.insert_context(context);
+    }
+
+    /// Remove the context for the context marker, and return whether it was present.
+    /// This is primarily for test ergonomics.
+    /// The renderer registered with with_rendered_context is removed too.
+    pub fn remove_context<M: ContextMarker>(&mut self) -> bool {
+        let type_id = TypeId::of::<M>();
+        self.renderers
+            .retain(|renderer| renderer.type_id != Some(type_id));
+        self.contexts.remove(&type_id).is_some()
+    }
+
+    /// Describe the environment for startup logs, without the context values to avoid leaking secrets.
+    /// The current environment is followed by a line per context, sorted by the type name,
+    /// with the environments having explicit values and whether a default value exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::environment::EnvironmentBuilder;
+    /// use env_type::is_debug::debug_context;
+    /// use env_type::types::EnvType;
+    ///
+    /// let env = EnvironmentBuilder::default()
+    ///  .current_env(EnvType::Prod)
+    ///  .with_context(debug_context().build())
+    ///  .build()
+    ///  .unwrap();
+    ///
+    /// assert_eq!(
+    ///     "current: Prod\n  env_type::is_debug::IsDebugContext: explicit: [Dev, Local], has_default: true",
+    ///     env.describe()
+    /// );
+    /// ```
+    pub fn describe(&self) -> String {
+        self.describe_entries(false)
+    }
+
+    /// Describe the environment with the context values, for local debugging.
+    /// The values are shown for the contexts registered with with_rendered_context,
+    /// because the others cannot be formatted.
+    pub fn describe_with_values(&self) -> String {
+        self.describe_entries(true)
+    }
+
+    fn describe_entries(&self, with_values: bool) -> String {
+        let mut entries: Vec<_> = self.contexts.values().collect();
+        entries.sort_unstable_by_key(|entry| entry.type_name);
+        let mut lines = vec![format!("current: {:?}", self.current_env())];
+        for entry in entries {
+            lines.push(format!(
+                "  {}: {}",
+                entry.type_name,
+                (entry.describe)(entry.context.as_ref())
+            ));
+            if with_values {
+                lines.push(match entry.describe_values {
+                    Some(describe_values) => describe_values(entry.context.as_ref()),
+                    None => "    <values hidden>".to_string(),
+                });
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// List the labels and the rendered current values of the contexts registered with a renderer,
+    /// in registration order. This is useful to dump the configuration at startup.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::environment::EnvironmentBuilder;
+    /// use env_type::is_debug::debug_context;
+    /// use env_type::types::EnvType;
+    ///
+    /// let env = EnvironmentBuilder::default()
+    ///  .current_env(EnvType::Dev)
+    ///  .with_rendered_context(debug_context().build())
+    ///  .build()
+    ///  .unwrap();
+    ///
+    /// for (label, value) in env.debug_snapshot() {
+    ///     println!("{label} = {value}");
+    /// }
+    /// ```
+    pub fn debug_snapshot(&self) -> Vec<(String, String)> {
+        self.renderers
+            .iter()
+            .map(|renderer| (renderer.label.to_string(), (renderer.render)(self)))
+            .collect()
+    }
+
+    /// Run the validators against the environment, and return the first error.
+    /// This is a hook to assert invariants at startup, e.g. debug must be off in production.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::environment::{Environment, EnvironmentBuilder};
+    /// use env_type::is_debug::{debug_context, IsDebug};
+    /// use env_type::types::{EnvError, EnvType};
+    ///
+    /// let env = EnvironmentBuilder::default()
+    ///  .current_env(EnvType::Prod)
+    ///  .with_context(debug_context().build())
+    ///  .build()
+    ///  .unwrap();
+    ///
+    /// let no_debug_in_prod = |env: &Environment| {
+    ///     if env.current_env().is_prod() && env.is_debug() {
+    ///         return Err(EnvError::InvalidConfig("debug is on in production".to_string()));
+    ///     }
+    ///     Ok(())
+    /// };
+    /// assert!(env.validate_with(&[no_debug_in_prod]).is_ok());
+    /// ```
+    pub fn validate_with<F>(&self, validators: &[F]) -> Result<(), EnvError>
+    where
+        F: Fn(&Environment) -> Result<(), EnvError>,
+    {
+        validators.iter().try_for_each(|validator| validator(self))
+    }
+
+    /// Initialize the process-wide global environment.
+    /// The global environment can be initialized only once,
+    /// a second call returns EnvError::GlobalAlreadyInitialized and keeps the first one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::environment::{Environment, EnvironmentBuilder};
+    /// use env_type::is_debug::{debug_context, IsDebug};
+    /// use env_type::types::EnvType;
+    ///
+    /// let env = EnvironmentBuilder::default()
+    ///  .current_env(EnvType::Dev)
+    ///  .with_context(debug_context().build())
+    ///  .build()
+    ///  .unwrap();
+    ///
+    /// assert!(Environment::init_global(env).is_ok());
+    /// assert!(Environment::global().is_debug());
+    /// assert_eq!(EnvType::Dev, Environment::global().current_env());
+    /// ```
+    pub fn init_global(env: Environment) -> Result<(), EnvError> {
+        GLOBAL_ENVIRONMENT
+            .set(env)
+            .map_err(|_| EnvError::GlobalAlreadyInitialized)
+    }
+
+    /// Get the process-wide global environment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the global environment is not initialized by Environment::init_global.
+    pub fn global() -> &'static Environment {
+        Self::try_global()
+            .expect("global environment is not initialized, call Environment::init_global first")
+    }
+
+    /// Get the process-wide global environment, if initialized.
+    pub fn try_global() -> Option<&'static Environment> {
+        GLOBAL_ENVIRONMENT.get()
+    }
 }
 
 /// Environment builder
@@ -46,7 +700,15 @@ impl Environment {
 #[derive(Default)]
 pub struct EnvironmentBuilder {
     current: Option<EnvType>,
-    contexts: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+    contexts: HashMap<TypeId, ContextEntry>,
+    /// Contexts that must be registered before build, with the type names for the error
+    required: Vec<(TypeId, &'static str)>,
+    /// Checks that the required contexts resolve a value for the current environment
+    required_values: Vec<ValueCheck>,
+    /// Labels and renderers for debug_snapshot
+    renderers: Vec<RendererEntry>,
+    /// Secrets loaded by with_secret, registered for the current environment in build
+    secrets: Vec<PendingSecret>,
 }
 
 /// EnvironmentBuilder implementation
@@ -89,10 +751,46 @@ pub struct EnvironmentBuilder {
 ///
 /// assert!(env.is_ok());
 /// let env = env.unwrap();
-/// assert_eq!(EnvType::Dev, *env.current_env());
+/// assert_eq!(EnvType::Dev, env.current_env());
 /// assert_eq!(Some("dev".to_string()), env.current_value::<TestContext>());
 /// ```
 impl EnvironmentBuilder {
+    /// Create a builder with the current environment from the `ENV` environment variable.
+    /// The current environment is Dev if the variable is not set or not parseable, like EnvType::from_env.
+    /// A later current_env call overrides it, the last call wins.
+    pub fn from_env() -> Self {
+        Self::default().current_env(EnvType::from_env())
+    }
+
+    /// Create a builder with the current environment from the environment variable of the key.
+    /// The current environment is Dev if the variable is not set or not parseable, like EnvType::from_env_key.
+    pub fn from_env_key<K: EnvKey>() -> Self {
+        Self::default().current_env(EnvType::from_env_key::<K>())
+    }
+
+    /// Create a builder with the current environment from the `ENV` environment variable,
+    /// and return the error instead of defaulting to Dev, like EnvType::try_from_env.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::environment::EnvironmentBuilder;
+    /// use env_type::types::{EnvError, EnvType};
+    ///
+    /// std::env::set_var("ENV", "unknown");
+    /// assert!(matches!(
+    ///     EnvironmentBuilder::try_from_env(),
+    ///     Err(EnvError::InvalidEnvValue { .. })
+    /// ));
+    ///
+    /// std::env::set_var("ENV", "prod");
+    /// let env = EnvironmentBuilder::try_from_env().unwrap().build().unwrap();
+    /// assert_eq!(EnvType::Prod, env.current_env());
+    /// ```
+    pub fn try_from_env() -> Result<Self, EnvError> {
+        Ok(Self::default().current_env(EnvType::try_from_env()?))
+    }
+
     pub fn current_env(mut self, env: EnvType) -> Self {
         self.current = Some(env);
         self
@@ -106,17 +804,991 @@ impl EnvironmentBuilder {
         self
     }
 
+    /// Set the current environment from the config, which can fail to yield an environment.
+    /// The error is returned where the config is supplied, instead of defaulting silently.
+    pub fn try_current_from<T>(mut self, config: T) -> Result<Self, EnvError>
+    where
+        EnvType: TryFrom<T, Error = EnvError>,
+    {
+        self.current = Some(EnvType::try_from(config)?);
+        Ok(self)
+    }
+
     pub fn with_context<M: ContextMarker>(mut self, context: Context<M>) -> Self {
-        self.contexts.insert(TypeId::of::<M>(), Arc::new(context));
+        self.add_context(context);
+        self
+    }
+
+    /// with_secret is a function that loads a secret from the secret source, e.g. the Shuttle secret store,
+    /// and registers it as the value of context M for the current environment.
+    /// The secret is read here and registered in build, so the current environment can be set before or after.
+    /// A registered secret replaces a context of M set with with_context.
+    /// If the secret is missing, the builder is returned unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::context::ContextMarker;
+    /// use env_type::environment::EnvironmentBuilder;
+    /// use env_type::types::EnvType;
+    /// use std::collections::BTreeMap;
+    ///
+    /// struct DatabaseUrl;
+    ///
+    /// impl ContextMarker for DatabaseUrl {
+    ///  type Value = String;
+    /// }
+    ///
+    /// let secrets = BTreeMap::from([("DATABASE_URL".to_string(), "postgres://prod".to_string())]);
+    /// let env = EnvironmentBuilder::default()
+    ///  .current_env(EnvType::Prod)
+    ///  .with_secret::<DatabaseUrl, _>(&secrets, "DATABASE_URL")
+    ///  .build()
+    ///  .unwrap();
+    ///
+    /// assert_eq!(Some("postgres://prod".to_string()), env.current_value::<DatabaseUrl>());
+    /// ```
+    pub fn with_secret<M, S>(mut self, store: &S, secret_key: &str) -> Self
+    where
+        M: ContextMarker<Value = String>,
+        S: SecretSource + ?Sized,
+    {
+        if let Some(secret) = store.secret(secret_key) {
+            self.secrets.push(Box::new(move |builder, current| {
+                builder.with_context(
+                    ContextBuilder::<M>::default()
+                        .with_value(current, secret)
+                        .build(),
+                )
+            }));
+        }
+        self
+    }
+
+    /// Register the context, and render its current value with Debug in debug_snapshot.
+    /// The values are also shown in Environment::describe_with_values.
+    /// The label is the type name of the context marker.
+    /// Registering the same context marker again replaces the context, and keeps a single renderer.
+    pub fn with_rendered_context<M>(mut self, context: Context<M>) -> Self
+    where
+        M: ContextMarker,
+        M::Value: std::fmt::Debug,
+    {
+        let type_id = TypeId::of::<M>();
+        self.contexts
+            .insert(type_id, ContextEntry::with_debug_values(context));
+        if !self
+            .renderers
+            .iter()
+            .any(|renderer| renderer.type_id == Some(type_id))
+        {
+            self.renderers.push(RendererEntry {
+                type_id: Some(type_id),
+                label: std::any::type_name::<M>(),
+                render: render_current::<M>,
+            });
+        }
         self
     }
 
-    pub fn build(self) -> Result<Environment, EnvError> {
+    /// Register the context, and include its current value in Environment::to_json.
+    #[cfg(feature = "serde")]
+    pub fn with_context_serializable<M>(mut self, context: Context<M>) -> Self
+    where
+        M: ContextMarker,
+        M::Value: serde::Serialize,
+    {
+        self.contexts
+            .insert(TypeId::of::<M>(), ContextEntry::with_serializable(context));
+        self
+    }
+
+    /// Register the label and the renderer for debug_snapshot.
+    pub fn with_renderer(mut self, label: &'static str, render: Renderer) -> Self {
+        self.renderers.push(RendererEntry {
+            type_id: None,
+            label,
+            render,
+        });
+        self
+    }
+
+    /// Register the context without consuming the builder,
+    /// so the builder can be passed to registration hooks by reference.
+    pub fn add_context<M: ContextMarker>(&mut self, context: Context<M>) -> &mut Self {
+        self.contexts
+            .insert(TypeId::of::<M>(), ContextEntry::new(context));
+        self
+    }
+
+    /// Require the context for the context marker to be registered.
+    /// build returns EnvError::MissingRequiredContext if the context is not registered,
+    /// so a forgotten context is caught at startup instead of as a None at runtime.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::environment::EnvironmentBuilder;
+    /// use env_type::is_debug::IsDebugContext;
+    /// use env_type::types::{EnvError, EnvType};
+    ///
+    /// let env = EnvironmentBuilder::default()
+    ///  .current_env(EnvType::Dev)
+    ///  .require_context::<IsDebugContext>()
+    ///  .build();
+    ///
+    /// assert!(matches!(env, Err(EnvError::MissingRequiredContext { .. })));
+    /// ```
+    pub fn require_context<M: ContextMarker>(mut self) -> Self {
+        self.required
+            .push((TypeId::of::<M>(), std::any::type_name::<M>()));
+        self
+    }
+
+    /// Require the context for the context marker to be registered,
+    /// and to resolve a value for the current environment, explicitly or by the default value.
+    /// build returns EnvError::ContextValueNotFound if the context has no value for the current environment.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::cache_control::{cache_control_context, CacheControlContext};
+    /// use env_type::environment::EnvironmentBuilder;
+    /// use env_type::types::EnvType;
+    ///
+    /// let env = EnvironmentBuilder::default()
+    ///  .current_env(EnvType::Prod)
+    ///  .with_context(cache_control_context().build())
+    ///  .require_value::<CacheControlContext>()
+    ///  .build();
+    ///
+    /// assert!(env.is_ok());
+    /// ```
+    pub fn require_value<M: ContextMarker>(mut self) -> Self {
+        self.required_values.push(check_current_value::<M>);
+        self.require_context::<M>()
+    }
+
+    pub fn build(mut self) -> Result<Environment, EnvError> {
         let current = self.current.ok_or(EnvError::NoCurrentEnv)?;
+        for secret in std::mem::take(&mut self.secrets) {
+            self = secret(self, current);
+        }
+        if let Some((_, type_name)) = self
+            .required
+            .iter()
+            .find(|(type_id, _)| !self.contexts.contains_key(type_id))
+        {
+            return Err(EnvError::MissingRequiredContext { name: type_name });
+        }
 
-        Ok(Environment {
+        let env = Environment {
             current,
+            current_override: CurrentOverride::default(),
             contexts: self.contexts,
-        })
+            renderers: self.renderers,
+        };
+        for check in &self.required_values {
+            check(&env)?;
+        }
+        Ok(env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_debug::{debug_context, debug_context_for, IsDebug, IsDebugContext};
+    use crate::testing::ScopedEnv;
+    use std::thread;
+
+    #[test]
+    fn test_value_ref() {
+        struct NoClone(Vec<String>);
+        impl Clone for NoClone {
+            fn clone(&self) -> Self {
+                panic!("the value must not be cloned");
+            }
+        }
+
+        struct FlagsContext;
+        impl ContextMarker for FlagsContext {
+            type Value = NoClone;
+        }
+
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .with_context(
+                ContextBuilder::<FlagsContext>::default()
+                    .with_value(EnvType::Prod, NoClone(vec!["beta".to_string()]))
+                    .with_default(NoClone(vec![]))
+                    .build(),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(env.current_value_ref::<FlagsContext>().unwrap().0, ["beta"]);
+        assert!(env
+            .value_ref::<FlagsContext>(&EnvType::Dev)
+            .unwrap()
+            .0
+            .is_empty());
+        assert!(env.value_ref::<IsDebugContext>(&EnvType::Dev).is_none());
+    }
+
+    #[test]
+    fn test_overlay_string_context() {
+        struct UrlContext;
+        impl ContextMarker for UrlContext {
+            type Value = String;
+        }
+
+        let mut env = EnvironmentBuilder::default()
+            .current_env(EnvType::Test)
+            .with_context(
+                ContextBuilder::<UrlContext>::default()
+                    .with_value(EnvType::Prod, "https://example.com".to_string())
+                    .build(),
+            )
+            .build()
+            .unwrap();
+        env.overlay_string_context::<UrlContext>(HashMap::from([(
+            EnvType::Test,
+            "http://localhost".to_string(),
+        )]));
+
+        assert_eq!(
+            env.current_value::<UrlContext>(),
+            Some("http://localhost".to_string())
+        );
+        assert_eq!(
+            env.value::<UrlContext>(&EnvType::Prod),
+            Some("https://example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_overlay_keeps_value_hooks() {
+        struct UrlContext;
+        impl ContextMarker for UrlContext {
+            type Value = String;
+        }
+
+        let mut env = EnvironmentBuilder::default()
+            .current_env(EnvType::Test)
+            .with_rendered_context(
+                ContextBuilder::<UrlContext>::default()
+                    .with_value(EnvType::Prod, "https://example.com".to_string())
+                    .build(),
+            )
+            .build()
+            .unwrap();
+        env.overlay_string_context::<UrlContext>(HashMap::from([(
+            EnvType::Test,
+            "http://localhost".to_string(),
+        )]));
+
+        let described = env.describe_with_values();
+        assert!(!described.contains("<values hidden>"), "{described}");
+        assert!(
+            described.contains("Test = \"http://localhost\""),
+            "{described}"
+        );
+
+        // a context registered without the hooks stays hidden
+        env.remove_context::<UrlContext>();
+        env.insert_context(ContextBuilder::<UrlContext>::default().build());
+        assert!(env.describe_with_values().contains("<values hidden>"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_overlay_keeps_serializable() {
+        struct UrlContext;
+        impl ContextMarker for UrlContext {
+            type Value = String;
+        }
+
+        let mut env = EnvironmentBuilder::default()
+            .current_env(EnvType::Test)
+            .with_context_serializable(ContextBuilder::<UrlContext>::default().build())
+            .build()
+            .unwrap();
+        env.overlay_string_context::<UrlContext>(HashMap::from([(
+            EnvType::Test,
+            "http://localhost".to_string(),
+        )]));
+        assert_eq!(
+            env.to_json()["contexts"]
+                ["env_type::environment::tests::test_overlay_keeps_serializable::UrlContext"]
+                ["current_value"],
+            "http://localhost"
+        );
+    }
+
+    #[test]
+    fn test_insert_context_replaces() {
+        use crate::cache_control::{cache_control_context, CacheControlContext};
+
+        fn register_plugins(builder: &mut EnvironmentBuilder) {
+            builder
+                .add_context(debug_context().build())
+                .add_context(cache_control_context().build());
+        }
+
+        let mut builder = EnvironmentBuilder::default().current_env(EnvType::Prod);
+        register_plugins(&mut builder);
+        let mut env = builder.build().unwrap();
+        assert!(!env.is_debug());
+        assert_eq!(env.cache_control(), "public, max-age=3600");
+
+        let previous = env.insert_context(debug_context_for([EnvType::Prod]).build());
+        assert_eq!(
+            previous.and_then(|context| context.get_for_env(&EnvType::Prod)),
+            Some(false)
+        );
+        assert!(env.is_debug());
+        assert_eq!(env.cache_control(), "public, max-age=3600");
+
+        let shared = env.clone();
+        let previous = env.insert_context(
+            ContextBuilder::<CacheControlContext>::default()
+                .with_default("no-cache".to_string())
+                .build(),
+        );
+        assert_eq!(
+            previous.and_then(|context| context.get_for_env(&EnvType::Prod)),
+            Some("public, max-age=3600".to_string())
+        );
+        assert_eq!(env.cache_control(), "no-cache");
+        assert_eq!(shared.cache_control(), "public, max-age=3600");
+        assert!(env.is_debug());
+
+        assert!(env.remove_context::<CacheControlContext>());
+        assert!(env.is_debug());
+    }
+
+    #[test]
+    fn test_context_introspection() {
+        use crate::cache_control::{cache_control_context, CacheControlContext};
+
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .with_context(debug_context().build())
+            .with_context(cache_control_context().build())
+            .build()
+            .unwrap();
+        assert!(env.has_context::<IsDebugContext>());
+        assert!(env.has_context::<CacheControlContext>());
+        assert_eq!(env.context_count(), 2);
+        assert_eq!(
+            env.context_names(),
+            vec![
+                "env_type::cache_control::CacheControlContext",
+                "env_type::is_debug::IsDebugContext"
+            ]
+        );
+        assert_eq!(
+            format!("{env:?}"),
+            "Environment { current: Prod, context_count: 2, contexts: [\"env_type::cache_control::CacheControlContext\", \"env_type::is_debug::IsDebugContext\"] }"
+        );
+
+        let mut env = env;
+        env.remove_context::<CacheControlContext>();
+        assert!(!env.has_context::<CacheControlContext>());
+        assert_eq!(env.context_count(), 1);
+        let debug = format!("{env:?}");
+        assert!(debug.contains("current: Prod"), "{debug}");
+        assert!(debug.contains("context_count: 1"), "{debug}");
+    }
+
+    #[test]
+    fn test_mutable_setters() {
+        let mut env = EnvironmentBuilder::default()
+            .current_env(EnvType::Dev)
+            .with_context(debug_context().build())
+            .build()
+            .unwrap();
+        assert!(env.is_debug());
+
+        env.set_current(EnvType::Prod);
+        assert_eq!(EnvType::Prod, env.current_env());
+        assert!(!env.is_debug());
+
+        env.insert_context(debug_context().with_value(EnvType::Prod, true).build());
+        assert!(env.is_debug());
+
+        assert!(env.remove_context::<IsDebugContext>());
+        assert!(!env.remove_context::<IsDebugContext>());
+        assert!(env.context::<IsDebugContext>().is_none());
+    }
+
+    #[test]
+    fn test_from_env() {
+        struct BuilderEnv;
+        impl EnvKey for BuilderEnv {
+            fn key() -> &'static str {
+                "TEST_BUILDER_ENV"
+            }
+        }
+
+        let mut guard = ScopedEnv::set("TEST_BUILDER_ENV", "stg");
+        let env = EnvironmentBuilder::from_env_key::<BuilderEnv>()
+            .build()
+            .unwrap();
+        assert_eq!(EnvType::Stg, env.current_env());
+
+        guard.remove_var("TEST_BUILDER_ENV");
+        let env = EnvironmentBuilder::from_env_key::<BuilderEnv>()
+            .build()
+            .unwrap();
+        assert_eq!(EnvType::Dev, env.current_env());
+
+        // the last write wins
+        let env = EnvironmentBuilder::from_env()
+            .current_env(EnvType::Prod)
+            .build()
+            .unwrap();
+        assert_eq!(EnvType::Prod, env.current_env());
+    }
+
+    #[test]
+    fn test_try_current_from() {
+        struct Config(Option<&'static str>);
+
+        impl TryFrom<Config> for EnvType {
+            type Error = EnvError;
+
+            fn try_from(config: Config) -> Result<Self, Self::Error> {
+                config
+                    .0
+                    .ok_or(EnvError::EnvVarNotSet {
+                        key: "ENV".to_string(),
+                    })
+                    .and_then(EnvType::from_str_lenient)
+            }
+        }
+
+        let env = EnvironmentBuilder::default()
+            .try_current_from(Config(Some("prod")))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(EnvType::Prod, env.current_env());
+
+        let result = EnvironmentBuilder::default().try_current_from(Config(None));
+        assert!(matches!(result, Err(EnvError::EnvVarNotSet { key }) if key == "ENV"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_to_json() {
+        use crate::cache_control::cache_control_context;
+
+        struct UrlContext;
+
+        impl ContextMarker for UrlContext {
+            type Value = String;
+        }
+
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .with_context_serializable(debug_context().build())
+            .with_context_serializable(
+                ContextBuilder::<UrlContext>::default()
+                    .with_value(EnvType::Dev, "http://localhost".to_string())
+                    .build(),
+            )
+            .with_context(cache_control_context().build())
+            .build()
+            .unwrap();
+        assert_eq!(
+            env.to_json(),
+            serde_json::json!({
+                "current": "production",
+                "contexts": {
+                    "env_type::cache_control::CacheControlContext": {
+                        "has_default": true,
+                        "envs": ["staging", "production"],
+                    },
+                    "env_type::environment::tests::test_to_json::UrlContext": {
+                        "current_value": null,
+                        "has_default": false,
+                        "envs": ["develop"],
+                    },
+                    "env_type::is_debug::IsDebugContext": {
+                        "current_value": false,
+                        "has_default": true,
+                        "envs": ["develop", "local"],
+                    },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_describe() {
+        use crate::cache_control::cache_control_context;
+
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .with_rendered_context(debug_context().build())
+            .with_context(cache_control_context().build())
+            .build()
+            .unwrap();
+        assert_eq!(
+            env.describe(),
+            [
+                "current: Prod",
+                "  env_type::cache_control::CacheControlContext: explicit: [Stg, Prod], has_default: true",
+                "  env_type::is_debug::IsDebugContext: explicit: [Dev, Local], has_default: true",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            env.describe_with_values(),
+            [
+                "current: Prod",
+                "  env_type::cache_control::CacheControlContext: explicit: [Stg, Prod], has_default: true",
+                "    <values hidden>",
+                "  env_type::is_debug::IsDebugContext: explicit: [Dev, Local], has_default: true",
+                "    Dev = true",
+                "    Local = true",
+                "    default = false",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_debug_snapshot() {
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Dev)
+            .with_rendered_context(debug_context().build())
+            .with_renderer("current", |env| {
+                env.current_env().canonical_str().to_string()
+            })
+            .build()
+            .unwrap();
+        assert_eq!(
+            env.debug_snapshot(),
+            vec![
+                (
+                    std::any::type_name::<IsDebugContext>().to_string(),
+                    "true".to_string()
+                ),
+                ("current".to_string(), "develop".to_string())
+            ]
+        );
+
+        // removing the context removes its renderer
+        let mut env = env;
+        env.remove_context::<IsDebugContext>();
+        assert_eq!(
+            env.debug_snapshot(),
+            vec![("current".to_string(), "develop".to_string())]
+        );
+
+        // registering the context again keeps a single renderer
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Dev)
+            .with_rendered_context(debug_context().build())
+            .with_rendered_context(debug_context_for([EnvType::Prod]).build())
+            .build()
+            .unwrap();
+        assert_eq!(
+            env.debug_snapshot(),
+            vec![(
+                std::any::type_name::<IsDebugContext>().to_string(),
+                "false".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_require_context() {
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Dev)
+            .with_context(debug_context().build())
+            .require_context::<IsDebugContext>()
+            .build();
+        assert!(env.is_ok());
+
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Dev)
+            .require_context::<IsDebugContext>()
+            .build();
+        assert_eq!(
+            env.err(),
+            Some(EnvError::MissingRequiredContext {
+                name: std::any::type_name::<IsDebugContext>()
+            })
+        );
+    }
+
+    #[test]
+    fn test_require_value() {
+        use crate::context::ContextBuilder;
+
+        struct UrlContext;
+        impl ContextMarker for UrlContext {
+            type Value = String;
+        }
+
+        let url_context = || {
+            ContextBuilder::<UrlContext>::default()
+                .with_value(EnvType::Dev, "http://localhost".to_string())
+                .build()
+        };
+
+        // missing context
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Dev)
+            .require_value::<UrlContext>()
+            .build();
+        assert!(matches!(env, Err(EnvError::MissingRequiredContext { .. })));
+
+        // present context with a value
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Dev)
+            .with_context(url_context())
+            .require_value::<UrlContext>()
+            .build();
+        assert!(env.is_ok());
+
+        // present context without a value for the current environment
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .with_context(url_context())
+            .require_value::<UrlContext>()
+            .build();
+        assert_eq!(
+            env.err(),
+            Some(EnvError::ContextValueNotFound {
+                context: std::any::type_name::<UrlContext>().to_string(),
+                env: EnvType::Prod,
+                tried: vec![EnvType::Prod]
+            })
+        );
+
+        // the default value is enough
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .with_context(
+                url_context()
+                    .into_builder()
+                    .with_default("https://example.com".to_string())
+                    .build(),
+            )
+            .require_value::<UrlContext>()
+            .build();
+        assert!(env.is_ok());
+    }
+
+    #[test]
+    fn test_validate_with() {
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .with_context(debug_context().with_value(EnvType::Prod, true).build())
+            .build()
+            .unwrap();
+
+        let no_debug_in_prod = |env: &Environment| {
+            if env.current_env().is_prod() && env.is_debug() {
+                return Err(EnvError::InvalidConfig(
+                    "debug is on in production".to_string(),
+                ));
+            }
+            Ok(())
+        };
+        let always_ok = |_: &Environment| Ok(());
+        type Validator<'a> = &'a dyn Fn(&Environment) -> Result<(), EnvError>;
+        let validators: [Validator; 2] = [&always_ok, &no_debug_in_prod];
+
+        assert_eq!(
+            env.validate_with(&validators),
+            Err(EnvError::InvalidConfig(
+                "debug is on in production".to_string()
+            ))
+        );
+        assert_eq!(env.validate_with(&validators[..1]), Ok(()));
+    }
+
+    #[test]
+    fn test_clone_across_threads() {
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Dev)
+            .with_context(debug_context().build())
+            .build()
+            .unwrap();
+        let context = env.context::<IsDebugContext>().unwrap() as *const Context<IsDebugContext>;
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let env = env.clone();
+                thread::spawn(move || {
+                    let shared = env.context::<IsDebugContext>().unwrap()
+                        as *const Context<IsDebugContext> as usize;
+                    (
+                        shared,
+                        env.current_value::<IsDebugContext>(),
+                        env.is_debug(),
+                    )
+                })
+            })
+            .collect();
+        for handle in handles {
+            let (shared, value, is_debug) = handle.join().unwrap();
+            assert_eq!(shared, context as usize);
+            assert_eq!(value, Some(true));
+            assert!(is_debug);
+        }
+    }
+
+    #[test]
+    fn test_env_predicates() {
+        for current in EnvType::VARIANTS {
+            let env = EnvironmentBuilder::default()
+                .current_env(*current)
+                .build()
+                .unwrap();
+            let predicates = [
+                (EnvType::Dev, env.is_dev()),
+                (EnvType::Test, env.is_test()),
+                (EnvType::Stg, env.is_stg()),
+                (EnvType::Prod, env.is_prod()),
+                (EnvType::Local, env.is_local()),
+                (EnvType::Ci, env.is_ci()),
+            ];
+            for (env_type, is) in predicates {
+                assert_eq!(is, env_type == *current, "{current:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_try_current_value() {
+        struct PortContext;
+        impl ContextMarker for PortContext {
+            type Value = u16;
+        }
+
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .with_context(
+                ContextBuilder::<PortContext>::default()
+                    .with_value(EnvType::Prod, 443)
+                    .build(),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(env.try_current_value::<PortContext>(), Ok(443));
+
+        let mut env = env;
+        env.set_current(EnvType::Dev);
+        assert_eq!(
+            env.try_current_value::<PortContext>(),
+            Err(EnvError::ContextValueNotFound {
+                context: std::any::type_name::<PortContext>().to_string(),
+                env: EnvType::Dev,
+                tried: vec![EnvType::Dev],
+            })
+        );
+
+        env.remove_context::<PortContext>();
+        assert_eq!(
+            env.try_current_value::<PortContext>(),
+            Err(EnvError::MissingRequiredContext {
+                name: std::any::type_name::<PortContext>(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_current_value_or() {
+        struct PortContext;
+        impl ContextMarker for PortContext {
+            type Value = u16;
+        }
+
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .with_context(
+                ContextBuilder::<PortContext>::default()
+                    .with_value(EnvType::Prod, 443)
+                    .build(),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(env.current_value_or::<PortContext>(8080), 443);
+        assert_eq!(
+            env.current_value_or_else::<PortContext, _>(|| unreachable!()),
+            443
+        );
+
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .build()
+            .unwrap();
+        assert_eq!(env.current_value_or::<PortContext>(8080), 8080);
+        assert_eq!(env.current_value_or_else::<PortContext, _>(|| 3000), 3000);
+    }
+
+    #[test]
+    fn test_override_current() {
+        struct UrlContext;
+        impl ContextMarker for UrlContext {
+            type Value = &'static str;
+        }
+
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Dev)
+            .with_context(debug_context().build())
+            .with_context(
+                ContextBuilder::<UrlContext>::default()
+                    .with_value(EnvType::Dev, "http://localhost")
+                    .with_value(EnvType::Prod, "https://example.com")
+                    .build(),
+            )
+            .build()
+            .unwrap();
+
+        {
+            let _prod = env.override_current(EnvType::Prod);
+            assert_eq!(env.current_env(), EnvType::Prod);
+            assert_eq!(
+                env.current_value::<UrlContext>(),
+                Some("https://example.com")
+            );
+            assert!(!env.is_debug());
+
+            thread::scope(|scope| {
+                for _ in 0..4 {
+                    scope.spawn(|| {
+                        assert_eq!(env.current_env(), EnvType::Prod);
+                        assert_eq!(
+                            env.current_value::<UrlContext>(),
+                            Some("https://example.com")
+                        );
+                    });
+                }
+            });
+
+            {
+                let custom = env.override_current(EnvType::Custom("preview"));
+                assert_eq!(env.current_env(), EnvType::Custom("preview"));
+                assert_eq!(env.current_value::<UrlContext>(), None);
+                // a clone keeps the current environment at the time of the clone
+                let cloned = env.clone();
+                drop(custom);
+                assert_eq!(cloned.current_env(), EnvType::Custom("preview"));
+            }
+            assert_eq!(env.current_env(), EnvType::Prod);
+        }
+        assert_eq!(env.current_env(), EnvType::Dev);
+        assert_eq!(env.current_value::<UrlContext>(), Some("http://localhost"));
+        assert!(env.is_debug());
+    }
+
+    #[test]
+    fn test_override_current_out_of_order() {
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Dev)
+            .build()
+            .unwrap();
+
+        let prod = env.override_current(EnvType::Prod);
+        let stg = env.override_current(EnvType::Stg);
+        let custom = env.override_current(EnvType::Custom("preview"));
+        // dropping an outer guard first only removes its own override
+        drop(prod);
+        assert_eq!(env.current_env(), EnvType::Custom("preview"));
+        drop(custom);
+        assert_eq!(env.current_env(), EnvType::Stg);
+        drop(stg);
+        assert_eq!(env.current_env(), EnvType::Dev);
+
+        // many custom overrides leave nothing behind
+        for _ in 0..1000 {
+            let _guard = env.override_current(EnvType::Custom("preview"));
+        }
+        assert_eq!(env.current_env(), EnvType::Dev);
+        assert!(env.current_override.lock().is_empty());
+    }
+
+    #[test]
+    fn test_override_current_scope() {
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Dev)
+            .build()
+            .unwrap();
+        let other = env.clone();
+
+        let _guard = env.override_current(EnvType::Prod);
+        // the override is scoped to the instance, shared by the threads but not by the clones
+        thread::scope(|scope| {
+            scope.spawn(|| assert_eq!(env.current_env(), EnvType::Prod));
+        });
+        assert_eq!(other.current_env(), EnvType::Dev);
+    }
+
+    /// A fake secret store, recording the looked up keys.
+    #[derive(Default)]
+    struct FakeSecretStore {
+        secrets: HashMap<String, String>,
+        lookups: Mutex<Vec<String>>,
+    }
+
+    impl SecretSource for FakeSecretStore {
+        fn secret(&self, key: &str) -> Option<String> {
+            self.lookups.lock().unwrap().push(key.to_string());
+            self.secrets.get(key).cloned()
+        }
+    }
+
+    #[test]
+    fn test_with_secret() {
+        struct DatabaseUrl;
+        impl ContextMarker for DatabaseUrl {
+            type Value = String;
+        }
+
+        let store = FakeSecretStore {
+            secrets: HashMap::from([("DATABASE_URL".to_string(), "postgres://prod".to_string())]),
+            ..Default::default()
+        };
+
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .with_secret::<DatabaseUrl, _>(&store, "DATABASE_URL")
+            .build()
+            .unwrap();
+        assert_eq!(
+            Some("postgres://prod".to_string()),
+            env.current_value::<DatabaseUrl>()
+        );
+        assert_eq!(None, env.value::<DatabaseUrl>(&EnvType::Dev));
+
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .with_secret::<DatabaseUrl, _>(&store, "MISSING")
+            .build()
+            .unwrap();
+        assert!(env.context::<DatabaseUrl>().is_none());
+
+        // the current environment set after the secret is used too
+        let env = EnvironmentBuilder::default()
+            .with_secret::<DatabaseUrl, _>(&store, "DATABASE_URL")
+            .current_env(EnvType::Prod)
+            .build()
+            .unwrap();
+        assert_eq!(
+            Some("postgres://prod".to_string()),
+            env.current_value::<DatabaseUrl>()
+        );
+        assert_eq!(None, env.value::<DatabaseUrl>(&EnvType::Dev));
+        assert_eq!(
+            *store.lookups.lock().unwrap(),
+            ["DATABASE_URL", "MISSING", "DATABASE_URL"]
+        );
     }
 }
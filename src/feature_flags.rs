@@ -0,0 +1,179 @@
+//! This is used to switch features on per environment.
+//! The flags for the current environment are the union of its own flags and the default flags.
+//!
+//! # Example
+//!
+//! ```
+//! use env_type::environment::EnvironmentBuilder;
+//! use env_type::feature_flags::{feature_flags_context, FeatureFlags};
+//! use env_type::types::EnvType;
+//!
+//! let env = EnvironmentBuilder::default()
+//!  .current_env(EnvType::Stg)
+//!  .with_context(
+//!     feature_flags_context()
+//!         .with_flags(EnvType::Stg, ["new_checkout", "beta_ui"])
+//!         .with_default_flags(["audit_log"])
+//!         .build(),
+//!  )
+//!  .build()
+//!  .unwrap();
+//!
+//! assert!(env.is_enabled("beta_ui"));
+//! assert!(env.is_enabled("audit_log"));
+//! assert!(!env.is_enabled("dark_mode"));
+//! ```
+use crate::context::{ContextBuilder, ContextMarker};
+use crate::environment::Environment;
+use crate::types::EnvType;
+use std::collections::BTreeSet;
+
+/// Flags is a set of feature flag names, iterated in sorted order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Flags(pub BTreeSet<String>);
+
+impl Flags {
+    /// Check if the flag is in the set.
+    pub fn contains(&self, flag: &str) -> bool {
+        self.0.contains(flag)
+    }
+
+    /// Iterate the flag names in sorted order.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for Flags {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        Self(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+pub struct FeatureFlagsContext;
+
+impl ContextMarker for FeatureFlagsContext {
+    type Value = Flags;
+}
+
+/// The empty feature flags context, add the flags with with_flags and with_default_flags.
+pub fn feature_flags_context() -> ContextBuilder<FeatureFlagsContext> {
+    ContextBuilder::<FeatureFlagsContext>::default()
+}
+
+impl ContextBuilder<FeatureFlagsContext> {
+    /// Set the flags for the environment type, replacing the flags already set for it.
+    pub fn with_flags<I, S>(self, env: EnvType, flags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.with_value(env, flags.into_iter().collect())
+    }
+
+    /// Set the default flags, which are enabled in every environment in addition to its own flags.
+    pub fn with_default_flags<I, S>(self, flags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.with_default(flags.into_iter().collect())
+    }
+}
+
+/// No flags, for the environments without the feature flags context.
+static NO_FLAGS: Flags = Flags(BTreeSet::new());
+
+pub trait FeatureFlags {
+    /// Check if the flag is enabled in the current environment.
+    fn is_enabled(&self, flag: &str) -> bool;
+
+    /// Iterate the flags enabled in the current environment in sorted order,
+    /// the union of the flags for the current environment and the default flags.
+    fn enabled_flags(&self) -> impl Iterator<Item = &str>;
+}
+
+impl FeatureFlags for Environment {
+    fn is_enabled(&self, flag: &str) -> bool {
+        self.enabled_flags().any(|enabled| enabled == flag)
+    }
+
+    fn enabled_flags(&self) -> impl Iterator<Item = &str> {
+        let context = self.context::<FeatureFlagsContext>();
+        // the value for the current environment is the default itself if it has no explicit flags
+        let current = context
+            .and_then(|context| context.get_for_env_ref(&self.current_env()))
+            .unwrap_or(&NO_FLAGS);
+        let default = context
+            .and_then(|context| context.default_value())
+            .unwrap_or(&NO_FLAGS);
+        current.0.union(&default.0).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::EnvironmentBuilder;
+
+    fn environment(current: EnvType) -> Environment {
+        EnvironmentBuilder::default()
+            .current_env(current)
+            .with_context(
+                feature_flags_context()
+                    .with_flags(EnvType::Dev, ["new_checkout", "beta_ui"])
+                    .with_flags(EnvType::Stg, ["new_checkout", "audit_log"])
+                    .with_default_flags(["audit_log", "dark_mode"])
+                    .build(),
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_union_with_default() {
+        let env = environment(EnvType::Dev);
+        assert_eq!(
+            env.enabled_flags().collect::<Vec<_>>(),
+            ["audit_log", "beta_ui", "dark_mode", "new_checkout"]
+        );
+        assert!(env.is_enabled("beta_ui"));
+        assert!(env.is_enabled("dark_mode"));
+
+        // a flag in both sets is enabled once
+        let env = environment(EnvType::Stg);
+        assert_eq!(
+            env.enabled_flags().collect::<Vec<_>>(),
+            ["audit_log", "dark_mode", "new_checkout"]
+        );
+
+        // the default flags only
+        let env = environment(EnvType::Prod);
+        assert_eq!(
+            env.enabled_flags().collect::<Vec<_>>(),
+            ["audit_log", "dark_mode"]
+        );
+        assert!(!env.is_enabled("beta_ui"));
+    }
+
+    #[test]
+    fn test_without_context() {
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Dev)
+            .build()
+            .unwrap();
+        assert_eq!(env.enabled_flags().count(), 0);
+        assert!(!env.is_enabled("beta_ui"));
+
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Dev)
+            .with_context(
+                feature_flags_context()
+                    .with_flags(EnvType::Prod, ["new_checkout"])
+                    .build(),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(env.enabled_flags().count(), 0);
+    }
+}
@@ -0,0 +1,271 @@
+//! This is used to map the environment type to figment profiles, and to get the environment type from a figment.
+//! The default mapping follows the Rocket convention, Dev is "debug" and Prod is "release",
+//! and the others are the canonical names, e.g. "staging".
+//!
+//! # Example
+//!
+//! ```
+//! use env_type::figment::EnvTypeProvider;
+//! use env_type::types::EnvType;
+//! use figment::providers::{Format, Toml};
+//! use figment::Figment;
+//!
+//! let toml = "[release]\nport = 443\n[debug]\nport = 3000\n";
+//! let figment = Figment::from(Toml::string(toml).nested())
+//!     .merge(EnvTypeProvider::new(EnvType::Prod));
+//! assert_eq!("release", figment.profile().as_str());
+//! assert_eq!(443, figment.extract_inner::<u16>("port").unwrap());
+//! ```
+use crate::types::{EnvError, EnvKey, EnvType};
+use ::figment::value::{Dict, Map, Value};
+use ::figment::{Error, Figment, Metadata, Profile, Provider};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// The default key of EnvTypeProvider.
+const DEFAULT_PROVIDER_KEY: &str = "env_type";
+
+/// ProfileMapping maps the environment types to the figment profiles.
+/// The environment types without an explicit profile use the canonical name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileMapping {
+    profiles: HashMap<EnvType, Profile>,
+}
+
+/// The default mapping, Dev is "debug" and Prod is "release".
+impl Default for ProfileMapping {
+    fn default() -> Self {
+        Self::canonical()
+            .with_profile(EnvType::Dev, "debug")
+            .with_profile(EnvType::Prod, "release")
+    }
+}
+
+impl ProfileMapping {
+    /// The mapping to the canonical names only, e.g. Dev is "develop" and Prod is "production".
+    pub fn canonical() -> Self {
+        Self {
+            profiles: HashMap::new(),
+        }
+    }
+
+    /// Map the environment type to the profile.
+    pub fn with_profile<P: Into<Profile>>(mut self, env: EnvType, profile: P) -> Self {
+        self.profiles.insert(env, profile.into());
+        self
+    }
+
+    /// Get the profile of the environment type.
+    pub fn profile(&self, env: &EnvType) -> Profile {
+        self.profiles
+            .get(env)
+            .cloned()
+            .unwrap_or_else(|| Profile::new(env.canonical_str()))
+    }
+
+    /// Get the environment type of the profile, the mapped profiles first, then parsed like FromStr.
+    /// Profiles are case-insensitive.
+    pub fn env_type(&self, profile: &Profile) -> Option<EnvType> {
+        let mut mapped: Vec<_> = self.profiles.iter().collect();
+        mapped.sort_by_key(|(env, _)| **env);
+        mapped
+            .into_iter()
+            .find(|(_, mapped)| *mapped == profile)
+            .map(|(env, _)| *env)
+            .or_else(|| EnvType::from_str(&profile.as_str().as_str().to_ascii_lowercase()).ok())
+    }
+}
+
+/// The profile of the default mapping, Dev is "debug" and Prod is "release".
+impl From<EnvType> for Profile {
+    fn from(env: EnvType) -> Self {
+        ProfileMapping::default().profile(&env)
+    }
+}
+
+impl EnvType {
+    /// EnvType::from_figment is a function that returns the environment type from the key "ENV" of the figment.
+    /// The key is looked up as is, then lowercased, because the figment Env provider lowercases the keys.
+    ///
+    /// Only the selected profile, the default and the global profiles are visible,
+    /// so a key set under another profile only is EnvError::KeyNotFound.
+    pub fn from_figment(figment: &Figment) -> Result<Self, EnvError> {
+        Self::from_figment_key::<Self>(figment)
+    }
+
+    /// EnvType::from_figment_key is a version of from_figment with the key of K.
+    pub fn from_figment_key<K: EnvKey>(figment: &Figment) -> Result<Self, EnvError> {
+        let key = K::key();
+        let value = [key.to_string(), key.to_ascii_lowercase()]
+            .iter()
+            .find_map(|key| figment.find_value(key).ok())
+            .ok_or_else(|| EnvError::KeyNotFound {
+                path: format!("figment profile {}", figment.profile()),
+                key: key.to_string(),
+            })?;
+        let invalid = |value: String| EnvError::InvalidEnvValue {
+            key: key.to_string(),
+            value,
+        };
+        match value.into_string() {
+            Some(value) => Self::from_str(&value).map_err(|_| invalid(value)),
+            None => Err(invalid("<not a string>".to_string())),
+        }
+    }
+}
+
+/// EnvTypeProvider is a figment provider of the environment type.
+/// It selects the profile of the environment type when merged, so the per-profile blocks of the other providers apply,
+/// and sets the canonical name at the key "env_type" of the default profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvTypeProvider {
+    env: EnvType,
+    key: String,
+    mapping: ProfileMapping,
+}
+
+impl EnvTypeProvider {
+    /// Create the provider of the environment type with the default mapping and key.
+    pub fn new(env: EnvType) -> Self {
+        Self {
+            env,
+            key: DEFAULT_PROVIDER_KEY.to_string(),
+            mapping: ProfileMapping::default(),
+        }
+    }
+
+    /// Set the key of the environment type.
+    pub fn with_key(mut self, key: &str) -> Self {
+        self.key = key.to_string();
+        self
+    }
+
+    /// Set the mapping to the profiles.
+    pub fn with_mapping(mut self, mapping: ProfileMapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+}
+
+impl Provider for EnvTypeProvider {
+    fn metadata(&self) -> Metadata {
+        Metadata::named("EnvType")
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, Error> {
+        let dict = Dict::from([(self.key.clone(), Value::from(self.env.canonical_str()))]);
+        Ok(Map::from([(Profile::Default, dict)]))
+    }
+
+    fn profile(&self) -> Option<Profile> {
+        Some(self.mapping.profile(&self.env))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::figment::providers::{Format, Toml};
+
+    const PROFILES: &str = "
+[default]
+port = 80
+
+[debug]
+port = 3000
+
+[release]
+port = 443
+ENV = \"prod\"
+
+[staging]
+port = 8080
+";
+
+    #[test]
+    fn test_profile() {
+        assert_eq!(Profile::from(EnvType::Dev), "debug");
+        assert_eq!(Profile::from(EnvType::Prod), "release");
+        assert_eq!(Profile::from(EnvType::Stg), "staging");
+        assert_eq!(Profile::from(EnvType::Custom("preview")), "preview");
+
+        let mapping = ProfileMapping::canonical().with_profile(EnvType::Dev, "dev");
+        assert_eq!(mapping.profile(&EnvType::Dev), "dev");
+        assert_eq!(mapping.profile(&EnvType::Prod), "production");
+    }
+
+    #[test]
+    fn test_env_type() {
+        let mapping = ProfileMapping::default();
+        assert_eq!(mapping.env_type(&Profile::new("debug")), Some(EnvType::Dev));
+        assert_eq!(
+            mapping.env_type(&Profile::new("RELEASE")),
+            Some(EnvType::Prod)
+        );
+        assert_eq!(
+            mapping.env_type(&Profile::new("staging")),
+            Some(EnvType::Stg)
+        );
+        assert_eq!(mapping.env_type(&Profile::new("unknown")), None);
+        for env in EnvType::VARIANTS {
+            assert_eq!(mapping.env_type(&mapping.profile(env)), Some(*env));
+        }
+    }
+
+    #[test]
+    fn test_from_figment() {
+        let figment = Figment::from(Toml::string("ENV = \"stg\"\nport = 80"));
+        assert_eq!(EnvType::from_figment(&figment), Ok(EnvType::Stg));
+
+        let figment = Figment::from(Toml::string("env = \"t\""));
+        assert_eq!(EnvType::from_figment(&figment), Ok(EnvType::Test));
+
+        let figment = Figment::from(Toml::string("ENV = \"banana\""));
+        assert_eq!(
+            EnvType::from_figment(&figment),
+            Err(EnvError::InvalidEnvValue {
+                key: "ENV".to_string(),
+                value: "banana".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_figment_non_selected_profile() {
+        // the key is only under the release profile, and the default profile is selected
+        let figment = Figment::from(Toml::string(PROFILES).nested());
+        assert_eq!(
+            EnvType::from_figment(&figment),
+            Err(EnvError::KeyNotFound {
+                path: "figment profile default".to_string(),
+                key: "ENV".to_string()
+            })
+        );
+        assert_eq!(
+            EnvType::from_figment(&figment.select("release")),
+            Ok(EnvType::Prod)
+        );
+    }
+
+    #[test]
+    fn test_provider() {
+        let figment = Figment::from(Toml::string(PROFILES).nested())
+            .merge(EnvTypeProvider::new(EnvType::Stg));
+        assert_eq!(figment.profile(), "staging");
+        assert_eq!(figment.extract_inner::<u16>("port").unwrap(), 8080);
+        assert_eq!(
+            figment.extract_inner::<String>("env_type").unwrap(),
+            "staging"
+        );
+
+        let figment = Figment::from(Toml::string(PROFILES).nested())
+            .merge(EnvTypeProvider::new(EnvType::Test).with_key("ENV"));
+        assert_eq!(figment.extract_inner::<u16>("port").unwrap(), 80);
+        assert_eq!(EnvType::from_figment(&figment), Ok(EnvType::Test));
+
+        let mapping = ProfileMapping::canonical().with_profile(EnvType::Dev, "release");
+        let figment = Figment::from(Toml::string(PROFILES).nested())
+            .merge(EnvTypeProvider::new(EnvType::Dev).with_mapping(mapping));
+        assert_eq!(figment.extract_inner::<u16>("port").unwrap(), 443);
+    }
+}
@@ -17,12 +17,12 @@
 ///
 /// assert!(env.is_ok());
 /// let env = env.unwrap();
-/// assert_eq!(EnvType::Dev, *env.current_env());
+/// assert_eq!(EnvType::Dev, env.current_env());
 /// assert_eq!(true, env.is_debug());
 /// ```
 use crate::context::{ContextBuilder, ContextMarker};
 use crate::environment::Environment;
-use crate::types::EnvType;
+use crate::types::{EnvKey, EnvType};
 
 pub struct IsDebugContext;
 
@@ -30,18 +30,399 @@ impl ContextMarker for IsDebugContext {
     type Value = bool;
 }
 
+/// The debug environments of the default policy, shared by debug_context and IsDebug for EnvType.
+const DEFAULT_DEBUG_ENVS: [EnvType; 2] = [EnvType::Dev, EnvType::Local];
+
+/// The default debug context, Dev and Local are debug environments.
 pub fn debug_context() -> ContextBuilder<IsDebugContext> {
+    debug_context_for(DEFAULT_DEBUG_ENVS)
+}
+
+/// The debug context with the given debug environments, the other environments are not debug.
+///
+/// # Example
+///
+/// ```
+/// use env_type::is_debug::debug_context_for;
+/// use env_type::types::EnvType;
+///
+/// let context = debug_context_for([EnvType::Dev, EnvType::Stg]).build();
+/// assert_eq!(Some(true), context.get_for_env(&EnvType::Stg));
+/// assert_eq!(Some(false), context.get_for_env(&EnvType::Prod));
+/// ```
+pub fn debug_context_for<I>(envs: I) -> ContextBuilder<IsDebugContext>
+where
+    I: IntoIterator<Item = EnvType>,
+{
     ContextBuilder::<IsDebugContext>::default()
-        .with_value(EnvType::Dev, true)
+        .with_values(envs, true)
         .with_default(false)
 }
 
+impl ContextBuilder<IsDebugContext> {
+    /// Replace the debug environments with the given ones, the other known environments are not debug.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::is_debug::debug_context;
+    /// use env_type::types::EnvType;
+    ///
+    /// let context = debug_context()
+    ///     .debug_envs([EnvType::Dev, EnvType::Test])
+    ///     .build();
+    /// assert_eq!(Some(true), context.get_for_env(&EnvType::Test));
+    /// assert_eq!(Some(false), context.get_for_env(&EnvType::Local));
+    /// ```
+    pub fn debug_envs<I>(self, envs: I) -> Self
+    where
+        I: IntoIterator<Item = EnvType>,
+    {
+        self.with_all_envs(false).with_values(envs, true)
+    }
+
+    /// Add the environment to the debug environments, keeping the ones already set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::is_debug::debug_context;
+    /// use env_type::types::EnvType;
+    ///
+    /// let context = debug_context().also_debug(EnvType::Stg).build();
+    /// assert_eq!(Some(true), context.get_for_env(&EnvType::Stg));
+    /// assert_eq!(Some(true), context.get_for_env(&EnvType::Dev));
+    /// assert_eq!(Some(false), context.get_for_env(&EnvType::Prod));
+    /// ```
+    pub fn also_debug(self, env: EnvType) -> Self {
+        self.with_value(env, true)
+    }
+}
+
+/// DebugOverrideKey is the default key of the debug override variable, "DEBUG".
+pub struct DebugOverrideKey;
+
+impl EnvKey for DebugOverrideKey {
+    fn key() -> &'static str {
+        "DEBUG"
+    }
+}
+
+/// Parse a debug flag, "1", "true" and "yes" are true, "0", "false" and "no" are false, case-insensitive.
+/// The other values, including the empty string, are None.
+///
+/// # Example
+///
+/// ```
+/// use env_type::is_debug::parse_debug_flag;
+///
+/// assert_eq!(Some(true), parse_debug_flag("TRUE"));
+/// assert_eq!(Some(false), parse_debug_flag("0"));
+/// assert_eq!(None, parse_debug_flag("maybe"));
+/// ```
+pub fn parse_debug_flag(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" => Some(true),
+        "0" | "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+impl ContextBuilder<IsDebugContext> {
+    /// Let the DEBUG environment variable override the debug context, see with_env_override_key.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::is_debug::debug_context;
+    /// use env_type::types::EnvType;
+    ///
+    /// std::env::set_var("DEBUG", "true");
+    /// let context = debug_context().with_env_override().build();
+    /// assert_eq!(Some(true), context.get_for_env(&EnvType::Prod));
+    /// ```
+    pub fn with_env_override(self) -> Self {
+        self.with_env_override_key::<DebugOverrideKey>()
+    }
+
+    /// Let the environment variable of the key override the debug context.
+    /// A truthy value (see parse_debug_flag) forces debug, a falsy value forces not debug,
+    /// and an unset or unknown value keeps the value of the context.
+    /// The values, the default value and the fallbacks of the context are kept under the override,
+    /// and a context without a default value gets false, so every environment follows the override.
+    /// The variable is read on the first lookup of each value and the result is kept,
+    /// so set it before the first lookup, or build the context again to pick up a change.
+    pub fn with_env_override_key<K: EnvKey>(self) -> Self {
+        let context = self.build();
+        let builder = if context.has_default() {
+            context.into_builder()
+        } else {
+            context.into_builder().with_default(false)
+        };
+        let read: fn() -> Option<bool> = debug_override::<K>;
+        builder.with_override(read)
+    }
+}
+
+/// Read the debug override variable of the key.
+fn debug_override<K: EnvKey>() -> Option<bool> {
+    std::env::var(K::key())
+        .ok()
+        .and_then(|value| parse_debug_flag(&value))
+}
+
 pub trait IsDebug {
     fn is_debug(&self) -> bool;
 }
 
+/// The default policy of debug_context, Dev and Local are debug environments.
+/// Environment::is_debug is authoritative when it has an IsDebugContext,
+/// so the two can disagree if the context was customized.
+///
+/// # Example
+///
+/// ```
+/// use env_type::is_debug::IsDebug;
+/// use env_type::types::EnvType;
+///
+/// assert!(EnvType::Dev.is_debug());
+/// assert!(!EnvType::Prod.is_debug());
+/// ```
+impl IsDebug for EnvType {
+    fn is_debug(&self) -> bool {
+        self.is_debug_with(&DEFAULT_DEBUG_ENVS)
+    }
+}
+
+impl EnvType {
+    /// EnvType::is_debug_with is a function that checks if the environment type is one of the given debug environments.
+    pub fn is_debug_with(&self, debug_envs: &[EnvType]) -> bool {
+        debug_envs.contains(self)
+    }
+}
+
 impl IsDebug for Environment {
     fn is_debug(&self) -> bool {
         self.current_value::<IsDebugContext>().unwrap_or(false)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::EnvironmentBuilder;
+    use crate::testing::ScopedEnv;
+
+    #[test]
+    fn test_debug_context() {
+        let context = debug_context().build();
+        for env in EnvType::VARIANTS {
+            let expected = matches!(env, EnvType::Dev | EnvType::Local);
+            assert_eq!(context.get_for_env(env), Some(expected), "{:?}", env);
+        }
+    }
+
+    #[test]
+    fn test_debug_context_for() {
+        let context = debug_context_for([EnvType::Dev, EnvType::Stg]).build();
+        for env in EnvType::VARIANTS {
+            let expected = matches!(env, EnvType::Dev | EnvType::Stg);
+            assert_eq!(context.get_for_env(env), Some(expected), "{:?}", env);
+        }
+    }
+
+    #[test]
+    fn test_env_type_is_debug() {
+        assert!(EnvType::Dev.is_debug());
+        assert!(EnvType::Local.is_debug());
+        assert!(!EnvType::Test.is_debug());
+        assert!(!EnvType::Stg.is_debug());
+        assert!(!EnvType::Prod.is_debug());
+        assert!(!EnvType::Ci.is_debug());
+        assert!(!EnvType::Custom("Preview").is_debug());
+
+        let context = debug_context().build();
+        for env in EnvType::VARIANTS {
+            assert_eq!(context.get_for_env(env), Some(env.is_debug()), "{:?}", env);
+        }
+    }
+
+    #[test]
+    fn test_env_type_is_debug_with() {
+        let debug_envs = [EnvType::Test, EnvType::Stg];
+        assert!(EnvType::Test.is_debug_with(&debug_envs));
+        assert!(EnvType::Stg.is_debug_with(&debug_envs));
+        assert!(!EnvType::Dev.is_debug_with(&debug_envs));
+        assert!(!EnvType::Prod.is_debug_with(&debug_envs));
+        assert!(!EnvType::Dev.is_debug_with(&[]));
+    }
+
+    #[test]
+    fn test_parse_debug_flag() {
+        for value in ["1", "true", "TRUE", "yes", "Yes"] {
+            assert_eq!(parse_debug_flag(value), Some(true), "{value}");
+        }
+        for value in ["0", "false", "False", "no", "NO"] {
+            assert_eq!(parse_debug_flag(value), Some(false), "{value}");
+        }
+        for value in ["", "2", "on", "debug"] {
+            assert_eq!(parse_debug_flag(value), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn test_with_env_override() {
+        let cases = [
+            (Some("1"), true, true),
+            (Some("yes"), true, true),
+            (Some("false"), false, false),
+            (Some("0"), false, false),
+            (Some("unknown"), true, false),
+            (None, true, false),
+        ];
+        for (value, dev, prod) in cases {
+            let _guard = match value {
+                Some(value) => ScopedEnv::set("DEBUG", value),
+                None => ScopedEnv::unset("DEBUG"),
+            };
+            for (current, expected) in [(EnvType::Dev, dev), (EnvType::Prod, prod)] {
+                let env = EnvironmentBuilder::default()
+                    .current_env(current)
+                    .with_context(debug_context().with_env_override().build())
+                    .build()
+                    .unwrap();
+                assert_eq!(env.is_debug(), expected, "{value:?} {current:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_with_env_override_key() {
+        struct OpsDebugKey;
+        impl EnvKey for OpsDebugKey {
+            fn key() -> &'static str {
+                "ENV_TYPE_TEST_OPS_DEBUG"
+            }
+        }
+
+        let mut guard = ScopedEnv::set("ENV_TYPE_TEST_OPS_DEBUG", "true");
+        guard.set_var("DEBUG", "false");
+        let context = debug_context()
+            .debug_envs([EnvType::Test])
+            .with_env_override_key::<OpsDebugKey>()
+            .build();
+        assert_eq!(context.get_for_env(&EnvType::Prod), Some(true));
+        assert_eq!(context.get_for_env(&EnvType::Custom("Preview")), Some(true));
+    }
+
+    #[test]
+    fn test_env_override_custom_env() {
+        let mut guard = ScopedEnv::unset("DEBUG");
+        let builder = || {
+            debug_context()
+                .also_debug(EnvType::Custom("Preview"))
+                .with_fallback(EnvType::Custom("Review"), EnvType::Dev)
+                .with_env_override()
+        };
+
+        // the values, the fallbacks and the default are kept without the variable
+        let context = builder().build();
+        assert_eq!(context.get_for_env(&EnvType::Custom("Preview")), Some(true));
+        assert_eq!(context.get_for_env(&EnvType::Custom("Review")), Some(true));
+        assert_eq!(context.get_for_env(&EnvType::Custom("Other")), Some(false));
+        assert_eq!(context.default_value(), Some(&false));
+
+        guard.set_var("DEBUG", "false");
+        let context = builder().build();
+        assert_eq!(
+            context.get_for_env(&EnvType::Custom("Preview")),
+            Some(false)
+        );
+        assert_eq!(context.get_for_env(&EnvType::Custom("Review")), Some(false));
+
+        guard.set_var("DEBUG", "true");
+        let context = builder().build();
+        assert_eq!(context.get_for_env(&EnvType::Custom("Other")), Some(true));
+    }
+
+    #[test]
+    fn test_env_override_without_default() {
+        let mut guard = ScopedEnv::unset("DEBUG");
+        let builder = || {
+            ContextBuilder::<IsDebugContext>::default()
+                .with_value(EnvType::Dev, true)
+                .with_env_override()
+        };
+
+        let context = builder().build();
+        assert_eq!(context.get_for_env(&EnvType::Dev), Some(true));
+        assert_eq!(
+            context.get_for_env(&EnvType::Custom("Preview")),
+            Some(false)
+        );
+
+        guard.set_var("DEBUG", "yes");
+        let context = builder().build();
+        assert_eq!(context.get_for_env(&EnvType::Prod), Some(true));
+        assert_eq!(context.get_for_env(&EnvType::Custom("Preview")), Some(true));
+    }
+
+    #[test]
+    fn test_without_env_override() {
+        let _guard = ScopedEnv::set("DEBUG", "true");
+        let context = debug_context().build();
+        assert_eq!(context.get_for_env(&EnvType::Prod), Some(false));
+        assert_eq!(context.get_for_env(&EnvType::Dev), Some(true));
+    }
+
+    #[test]
+    fn test_debug_envs() {
+        let context = debug_context()
+            .debug_envs([EnvType::Dev, EnvType::Test])
+            .build();
+        for env in EnvType::VARIANTS {
+            let expected = matches!(env, EnvType::Dev | EnvType::Test);
+            assert_eq!(context.get_for_env(env), Some(expected), "{:?}", env);
+        }
+        assert_eq!(
+            context.get_for_env(&EnvType::Custom("Preview")),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_also_debug() {
+        let context = debug_context()
+            .also_debug(EnvType::Stg)
+            .also_debug(EnvType::Custom("Preview"))
+            .build();
+        for env in EnvType::VARIANTS {
+            let expected = matches!(env, EnvType::Dev | EnvType::Local | EnvType::Stg);
+            assert_eq!(context.get_for_env(env), Some(expected), "{:?}", env);
+        }
+        assert_eq!(context.get_for_env(&EnvType::Custom("Preview")), Some(true));
+    }
+
+    #[test]
+    fn test_debug_envs_then_also_debug() {
+        let context = debug_context()
+            .debug_envs([EnvType::Test])
+            .also_debug(EnvType::Stg)
+            .build();
+        for env in EnvType::VARIANTS {
+            let expected = matches!(env, EnvType::Test | EnvType::Stg);
+            assert_eq!(context.get_for_env(env), Some(expected), "{:?}", env);
+        }
+    }
+
+    #[test]
+    fn test_debug_context_for_then_also_debug() {
+        let context = debug_context_for([EnvType::Test])
+            .also_debug(EnvType::Dev)
+            .build();
+        for env in EnvType::VARIANTS {
+            let expected = matches!(env, EnvType::Test | EnvType::Dev);
+            assert_eq!(context.get_for_env(env), Some(expected), "{:?}", env);
+        }
+    }
+}
@@ -12,12 +12,70 @@
 //! env-type crate has the following features:
 //!
 //! - `shuttle`: This feature is used to get the environment type from the secret store.
+//! - `web`: This feature is used to share the environment as web framework state.
+//! - `envfile`: This feature is used to get the environment type from a `.env` file.
+//! - `clap`: This feature is used to parse the environment type from a command line argument.
+//! - `serde`: This feature is used to serialize and deserialize the environment type, and to dump the environment as JSON.
+//! - `derive`: This feature is used to derive the EnvKey trait with `#[derive(EnvKey)]`.
+//! - `tracing`: This feature is used to set the tracing level filter from the environment type.
+//! - `dotenv`: This feature is used to load a `.env` file with dotenvy before resolving the environment type.
+//! - `async`: This feature is used to get the environment type from an async source, like a secrets backend.
+//! - `testing`: This feature is used to override environment variables safely in tests with `ScopedEnv`.
+//! - `tokio`: This feature is used to scope the environment to a tokio task with `task_local::scope`.
+//! - `axum`: This feature is used to inject the environment into axum requests and extract its values.
+//! - `actix`: This feature is used to share the environment as actix-web app data and extract its values.
+//! - `config-file`: This feature is used to get the environment type from a TOML or JSON config file.
+//! - `config`: This feature is used to get the environment type from a `config::Config`, and to inject it as a config source.
+//! - `figment`: This feature is used to map the environment type to figment profiles, and to get it from a figment.
 //! - `all`: This feature is used to enable all features.
+extern crate self as env_type;
+
+mod macros;
+
+pub mod build;
+pub mod cache_control;
 pub mod context;
 pub mod environment;
+pub mod resolver;
+pub mod secret;
 pub mod types;
 
+pub mod feature_flags;
 pub mod is_debug;
+pub mod log_level;
 
 #[cfg(feature = "shuttle")]
 pub mod secret_store;
+
+#[cfg(feature = "web")]
+pub mod web;
+
+#[cfg(feature = "envfile")]
+pub mod env_file;
+
+#[cfg(feature = "tracing")]
+pub mod tracing;
+
+#[cfg(feature = "dotenv")]
+pub mod dotenv;
+
+#[cfg(feature = "actix")]
+pub mod actix;
+
+#[cfg(feature = "axum")]
+pub mod axum;
+
+#[cfg(feature = "config-file")]
+pub mod config_file;
+
+#[cfg(feature = "config")]
+pub mod config;
+
+#[cfg(feature = "figment")]
+pub mod figment;
+
+#[cfg(feature = "tokio")]
+pub mod task_local;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
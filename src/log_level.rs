@@ -0,0 +1,149 @@
+//! This is used to determine the default log verbosity for the environment.
+//! The LogLevel is a small enum, so it can be mapped to any logging crate.
+//!
+//! # Example
+//!
+//! ```
+//! use env_type::log_level::LogLevel;
+//! use env_type::types::EnvType;
+//!
+//! assert_eq!(LogLevel::Warn, EnvType::Prod.default_log_level());
+//! assert_eq!("debug", EnvType::Dev.default_log_level().as_str());
+//! ```
+use crate::context::{ContextBuilder, ContextMarker};
+use crate::environment::Environment;
+use crate::types::EnvType;
+
+/// LogLevel is the log verbosity, from the most verbose to the least verbose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    /// LogLevel::as_str is a function that returns the lowercase name of the log level,
+    /// which is accepted by most logging crates, e.g. as a `RUST_LOG` directive.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+}
+
+impl EnvType {
+    /// EnvType::default_log_level is a function that returns the default log level for the environment type.
+    /// Dev and Local are Debug, Prod is Warn, and the others are Info.
+    pub fn default_log_level(&self) -> LogLevel {
+        match self {
+            EnvType::Dev | EnvType::Local => LogLevel::Debug,
+            EnvType::Test | EnvType::Stg | EnvType::Ci | EnvType::Custom(_) => LogLevel::Info,
+            EnvType::Prod => LogLevel::Warn,
+        }
+    }
+}
+
+pub struct LogLevelContext;
+
+impl ContextMarker for LogLevelContext {
+    type Value = LogLevel;
+}
+
+/// The default log level context, following EnvType::default_log_level for the known environments,
+/// except Test is Debug to show the details of failing tests.
+/// The other environments are Info. Override a level with with_value before building.
+///
+/// # Example
+///
+/// ```
+/// use env_type::log_level::{log_level_context, LogLevel};
+/// use env_type::types::EnvType;
+///
+/// let context = log_level_context()
+///     .with_value(EnvType::Stg, LogLevel::Debug)
+///     .build();
+/// assert_eq!(Some(LogLevel::Debug), context.get_for_env(&EnvType::Test));
+/// assert_eq!(Some(LogLevel::Debug), context.get_for_env(&EnvType::Stg));
+/// assert_eq!(Some(LogLevel::Warn), context.get_for_env(&EnvType::Prod));
+/// ```
+pub fn log_level_context() -> ContextBuilder<LogLevelContext> {
+    ContextBuilder::<LogLevelContext>::default()
+        .with_value_fn(|env| env.default_log_level())
+        .with_value(EnvType::Test, LogLevel::Debug)
+        .with_default(LogLevel::Info)
+}
+
+impl Environment {
+    /// Get the log level for the current environment from the log level context,
+    /// or EnvType::default_log_level if the context is not registered.
+    pub fn log_level(&self) -> LogLevel {
+        self.current_value::<LogLevelContext>()
+            .unwrap_or_else(|| self.current_env().default_log_level())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::EnvironmentBuilder;
+
+    #[test]
+    fn test_default_log_level() {
+        assert_eq!(EnvType::Dev.default_log_level(), LogLevel::Debug);
+        assert_eq!(EnvType::Test.default_log_level(), LogLevel::Info);
+        assert_eq!(EnvType::Stg.default_log_level(), LogLevel::Info);
+        assert_eq!(EnvType::Prod.default_log_level(), LogLevel::Warn);
+        assert_eq!(EnvType::Local.default_log_level(), LogLevel::Debug);
+        assert_eq!(EnvType::Ci.default_log_level(), LogLevel::Info);
+        assert_eq!(EnvType::Custom("demo").default_log_level(), LogLevel::Info);
+    }
+
+    #[test]
+    fn test_as_str() {
+        assert_eq!(LogLevel::Trace.as_str(), "trace");
+        assert_eq!(LogLevel::Debug.as_str(), "debug");
+        assert_eq!(LogLevel::Info.as_str(), "info");
+        assert_eq!(LogLevel::Warn.as_str(), "warn");
+        assert_eq!(LogLevel::Error.as_str(), "error");
+    }
+
+    #[test]
+    fn test_log_level_context() {
+        let context = log_level_context().build();
+        for env in EnvType::VARIANTS {
+            let expected = match env {
+                EnvType::Test => LogLevel::Debug,
+                _ => env.default_log_level(),
+            };
+            assert_eq!(context.get_for_env(env), Some(expected), "{env:?}");
+        }
+        assert_eq!(
+            context.get_for_env(&EnvType::Custom("demo")),
+            Some(LogLevel::Info)
+        );
+
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Test)
+            .with_context(
+                log_level_context()
+                    .with_value(EnvType::Test, LogLevel::Trace)
+                    .build(),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(env.log_level(), LogLevel::Trace);
+
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .build()
+            .unwrap();
+        assert_eq!(env.log_level(), LogLevel::Warn);
+    }
+}
@@ -0,0 +1,329 @@
+/// env_key! is a macro to declare EnvKey types in one line.
+/// The macro expands to a unit struct and the EnvKey implementation.
+/// The visibility, attributes and a doc string are optional,
+/// and several keys can be declared in one invocation separated by `;`.
+///
+/// # Example
+///
+/// ```
+/// use env_type::env_key;
+/// use env_type::types::EnvType;
+///
+/// env_key!(MyServiceEnv, "MY_SERVICE_ENV");
+///
+/// env_key! {
+///     pub DatabaseEnvKey, "DB_ENV", "Key used for the database tier";
+///     #[derive(Debug)]
+///     pub(crate) CacheEnvKey, "CACHE_ENV";
+/// }
+///
+/// std::env::set_var("MY_SERVICE_ENV", "Production");
+/// std::env::set_var("DB_ENV", "stg");
+/// assert_eq!(EnvType::Prod, EnvType::from_env_key::<MyServiceEnv>());
+/// assert_eq!(EnvType::Stg, EnvType::from_env_key::<DatabaseEnvKey>());
+/// ```
+#[macro_export]
+macro_rules! env_key {
+    () => {};
+    ($(#[$meta:meta])* $vis:vis $name:ident, $key:literal, $doc:literal $(; $($rest:tt)*)?) => {
+        #[doc = $doc]
+        $(#[$meta])*
+        $vis struct $name;
+
+        impl $crate::types::EnvKey for $name {
+            fn key() -> &'static str {
+                $key
+            }
+        }
+
+        $($crate::env_key!($($rest)*);)?
+    };
+    ($(#[$meta:meta])* $vis:vis $name:ident, $key:literal $(; $($rest:tt)*)?) => {
+        $(#[$meta])*
+        $vis struct $name;
+
+        impl $crate::types::EnvKey for $name {
+            fn key() -> &'static str {
+                $key
+            }
+        }
+
+        $($crate::env_key!($($rest)*);)?
+    };
+}
+
+/// define_context! is a macro to declare ContextMarker types in one line.
+/// The macro expands to a unit struct and the ContextMarker implementation with the value type.
+/// The visibility, attributes and a doc string are optional,
+/// and several markers can be declared in one invocation separated by `;`.
+///
+/// # Example
+///
+/// ```
+/// use env_type::context::ContextBuilder;
+/// use env_type::define_context;
+/// use env_type::types::EnvType;
+///
+/// define_context!(DatabaseUrl, String);
+///
+/// define_context! {
+///     pub ApiTimeout, u64, "Timeout of the API calls in seconds";
+///     pub(crate) Replicas, Vec<u16>;
+/// }
+///
+/// let context = ContextBuilder::<ApiTimeout>::default()
+///     .with_value(EnvType::Prod, 5)
+///     .with_default(30)
+///     .build();
+/// assert_eq!(Some(5), context.get_for_env(&EnvType::Prod));
+/// ```
+#[macro_export]
+macro_rules! define_context {
+    () => {};
+    ($(#[$meta:meta])* $vis:vis $name:ident, $value:ty, $doc:literal $(; $($rest:tt)*)?) => {
+        #[doc = $doc]
+        $(#[$meta])*
+        $vis struct $name;
+
+        impl $crate::context::ContextMarker for $name {
+            type Value = $value;
+        }
+
+        $($crate::define_context!($($rest)*);)?
+    };
+    ($(#[$meta:meta])* $vis:vis $name:ident, $value:ty $(; $($rest:tt)*)?) => {
+        $(#[$meta])*
+        $vis struct $name;
+
+        impl $crate::context::ContextMarker for $name {
+            type Value = $value;
+        }
+
+        $($crate::define_context!($($rest)*);)?
+    };
+}
+
+/// match_env! is a macro to match every environment type with named arms.
+/// All arms are required in the declaration order of EnvType,
+/// so a missing arm is a compile error, and the custom arm can bind the custom name.
+///
+/// # Example
+///
+/// ```
+/// use env_type::match_env;
+/// use env_type::types::EnvType;
+///
+/// let pool_size = match_env!(EnvType::Stg,
+///     dev => 1,
+///     test => 1,
+///     stg => 4,
+///     prod => 16,
+///     local => 1,
+///     ci => 2,
+///     custom => 1,
+/// );
+/// assert_eq!(4, pool_size);
+///
+/// let label = match_env!(EnvType::Custom("preview"),
+///     dev => "dev".to_string(),
+///     test => "test".to_string(),
+///     stg => "stg".to_string(),
+///     prod => "prod".to_string(),
+///     local => "local".to_string(),
+///     ci => "ci".to_string(),
+///     custom(name) => format!("custom:{name}"),
+/// );
+/// assert_eq!("custom:preview", label);
+/// ```
+#[macro_export]
+macro_rules! match_env {
+    ($env:expr,
+        dev => $dev:expr,
+        test => $test:expr,
+        stg => $stg:expr,
+        prod => $prod:expr,
+        local => $local:expr,
+        ci => $ci:expr,
+        custom($name:pat) => $custom:expr $(,)?
+    ) => {
+        match $env {
+            $crate::types::EnvType::Dev => $dev,
+            $crate::types::EnvType::Test => $test,
+            $crate::types::EnvType::Stg => $stg,
+            $crate::types::EnvType::Prod => $prod,
+            $crate::types::EnvType::Local => $local,
+            $crate::types::EnvType::Ci => $ci,
+            $crate::types::EnvType::Custom($name) => $custom,
+        }
+    };
+    ($env:expr,
+        dev => $dev:expr,
+        test => $test:expr,
+        stg => $stg:expr,
+        prod => $prod:expr,
+        local => $local:expr,
+        ci => $ci:expr,
+        custom => $custom:expr $(,)?
+    ) => {
+        $crate::match_env!($env,
+            dev => $dev,
+            test => $test,
+            stg => $stg,
+            prod => $prod,
+            local => $local,
+            ci => $ci,
+            custom(_) => $custom,
+        )
+    };
+}
+
+/// env_match! is a macro to match the current environment of an Environment, like match_env! with a default arm.
+/// The arms are keyed by the EnvType variants, several variants can share an arm with `|`,
+/// and the default arm `_` is required, so the variants without an arm are covered.
+///
+/// # Example
+///
+/// ```
+/// use env_type::env_match;
+/// use env_type::environment::EnvironmentBuilder;
+/// use env_type::types::EnvType;
+///
+/// let env = EnvironmentBuilder::default()
+///     .current_env(EnvType::Stg)
+///     .build()
+///     .unwrap();
+///
+/// let workers = env_match!(env, Dev | Local => 1, Stg => 4, Prod => 16, _ => 2);
+/// assert_eq!(4, workers);
+/// ```
+#[macro_export]
+macro_rules! env_match {
+    ($env:expr, $($($variant:ident)|+ => $value:expr,)* _ => $default:expr $(,)?) => {
+        match $env.current_env() {
+            $($($crate::types::EnvType::$variant)|+ => $value,)*
+            _ => $default,
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::context::ContextBuilder;
+    use crate::environment::EnvironmentBuilder;
+    use crate::testing::ScopedEnv;
+    use crate::types::{EnvKey, EnvType};
+
+    env_key!(SingleEnvKey, "TEST_MACRO_SINGLE_ENV");
+
+    env_key! {
+        /// Documented with an attribute.
+        pub FirstEnvKey, "TEST_MACRO_FIRST_ENV";
+        pub(crate) SecondEnvKey, "TEST_MACRO_SECOND_ENV", "Documented with a literal";
+    }
+
+    #[test]
+    fn test_env_key() {
+        assert_eq!(SingleEnvKey::key(), "TEST_MACRO_SINGLE_ENV");
+        assert_eq!(FirstEnvKey::key(), "TEST_MACRO_FIRST_ENV");
+        assert_eq!(SecondEnvKey::key(), "TEST_MACRO_SECOND_ENV");
+
+        let _guard = ScopedEnv::set("TEST_MACRO_SECOND_ENV", "t");
+        assert_eq!(EnvType::from_env_key::<SecondEnvKey>(), EnvType::Test);
+    }
+
+    define_context!(DatabaseUrl, String);
+
+    define_context! {
+        /// Documented with an attribute.
+        pub ApiTimeout, u64;
+        pub(crate) Replicas, Vec<u16>, "Documented with a literal";
+    }
+
+    #[test]
+    fn test_define_context() {
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .with_context(
+                ContextBuilder::<DatabaseUrl>::default()
+                    .with_value(EnvType::Prod, "postgres://prod".to_string())
+                    .build(),
+            )
+            .with_context(
+                ContextBuilder::<ApiTimeout>::default()
+                    .with_default(30)
+                    .build(),
+            )
+            .with_context(
+                ContextBuilder::<Replicas>::default()
+                    .with_value(EnvType::Prod, vec![1, 2])
+                    .build(),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(
+            env.current_value::<DatabaseUrl>(),
+            Some("postgres://prod".to_string())
+        );
+        assert_eq!(env.current_value::<ApiTimeout>(), Some(30));
+        assert_eq!(env.current_value::<Replicas>(), Some(vec![1, 2]));
+    }
+
+    #[test]
+    fn test_env_match() {
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .build()
+            .unwrap();
+        assert_eq!(env_match!(env, Dev => 1, Test => 2, _ => 0), 0);
+        assert_eq!(env_match!(&env, Dev | Prod => "hot", _ => "cold",), "hot");
+        assert_eq!(env_match!(env, _ => 3), 3);
+
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Test)
+            .build()
+            .unwrap();
+        assert_eq!(env_match!(env, Dev => 1, Test => 2, _ => 0), 2);
+    }
+
+    #[test]
+    fn test_match_env() {
+        let names: Vec<String> = EnvType::VARIANTS
+            .iter()
+            .chain([&EnvType::Custom("preview")])
+            .map(|env| {
+                match_env!(env,
+                    dev => "dev".to_string(),
+                    test => "test".to_string(),
+                    stg => "stg".to_string(),
+                    prod => "prod".to_string(),
+                    local => "local".to_string(),
+                    ci => "ci".to_string(),
+                    custom(name) => format!("custom:{name}"),
+                )
+            })
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "dev",
+                "test",
+                "stg",
+                "prod",
+                "local",
+                "ci",
+                "custom:preview"
+            ]
+        );
+
+        let size = match_env!(EnvType::Custom("preview"),
+            dev => 1,
+            test => 2,
+            stg => 3,
+            prod => 4,
+            local => 5,
+            ci => 6,
+            custom => 0
+        );
+        assert_eq!(size, 0);
+    }
+}
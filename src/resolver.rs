@@ -0,0 +1,159 @@
+//! This is used to resolve the environment type from several sources with an explicit precedence.
+//! The override is tried first, then the sources in the order they were added, and the default last.
+//! A source is skipped if its value is empty or not parseable, so the next one is tried.
+//!
+//! # Example
+//!
+//! ```
+//! use env_type::resolver::EnvResolver;
+//! use env_type::types::{EnvType, MapEnv};
+//! use std::collections::HashMap;
+//!
+//! let secrets = MapEnv(HashMap::from([("ENV".to_string(), "stg".to_string())]));
+//! let env = EnvResolver::default()
+//!     .with_override(None)
+//!     .with_env_key("ENV_TYPE_RESOLVER_EXAMPLE_UNSET")
+//!     .with_source(secrets)
+//!     .with_default(EnvType::Prod)
+//!     .resolve();
+//! assert_eq!(EnvType::Stg, env);
+//! ```
+use crate::types::{AsEnvStr, EnvKey, EnvType};
+use std::str::FromStr;
+
+/// A source of the environment string, tried by the resolver in order.
+type Source = Box<dyn Fn() -> String + Send + Sync>;
+
+/// EnvResolver is a builder of the precedence of the environment type sources.
+#[derive(Default)]
+pub struct EnvResolver {
+    /// The explicit environment type, which beats all sources
+    override_env: Option<EnvType>,
+    /// The sources in the order they are tried
+    sources: Vec<Source>,
+    /// The environment type if no source yields a value, Dev if not set
+    default: Option<EnvType>,
+}
+
+impl EnvResolver {
+    /// Set the explicit environment type, e.g. from a command line argument.
+    /// None keeps the sources in charge, so an optional argument can be passed through.
+    pub fn with_override(mut self, env: Option<EnvType>) -> Self {
+        self.override_env = env;
+        self
+    }
+
+    /// Add the environment variable of the key as a source, read when resolving.
+    pub fn with_env_key(mut self, key: &str) -> Self {
+        let key = key.to_string();
+        self.sources
+            .push(Box::new(move || std::env::var(&key).unwrap_or_default()));
+        self
+    }
+
+    /// Add the source with the default key of EnvType ("ENV"), e.g. a secret store.
+    pub fn with_source<S>(self, source: S) -> Self
+    where
+        S: AsEnvStr + Send + Sync + 'static,
+    {
+        self.with_source_key::<S, EnvType>(source)
+    }
+
+    /// Add the source with the key of K.
+    pub fn with_source_key<S, K>(mut self, source: S) -> Self
+    where
+        S: AsEnvStr + Send + Sync + 'static,
+        K: EnvKey,
+    {
+        self.sources
+            .push(Box::new(move || source.as_env_str::<K>()));
+        self
+    }
+
+    /// Set the environment type used if no source yields a value.
+    pub fn with_default(mut self, env: EnvType) -> Self {
+        self.default = Some(env);
+        self
+    }
+
+    /// Resolve the environment type, the override first, then the sources in order, and the default last.
+    pub fn resolve(&self) -> EnvType {
+        self.override_env
+            .or_else(|| {
+                self.sources
+                    .iter()
+                    .find_map(|source| EnvType::from_str(&source()).ok())
+            })
+            .or(self.default)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ScopedEnv;
+    use crate::types::MapEnv;
+    use std::collections::HashMap;
+
+    const KEY: &str = "ENV_TYPE_TEST_RESOLVER_ENV";
+
+    fn resolver() -> EnvResolver {
+        EnvResolver::default()
+            .with_env_key(KEY)
+            .with_default(EnvType::Stg)
+    }
+
+    #[test]
+    fn test_precedence() {
+        let mut guard = ScopedEnv::set(KEY, "test");
+        assert_eq!(
+            resolver().with_override(Some(EnvType::Prod)).resolve(),
+            EnvType::Prod
+        );
+        assert_eq!(resolver().with_override(None).resolve(), EnvType::Test);
+
+        guard.remove_var(KEY);
+        assert_eq!(resolver().resolve(), EnvType::Stg);
+    }
+
+    #[test]
+    fn test_sources_in_order() {
+        let mut guard = ScopedEnv::set(KEY, "unknown");
+        let secrets = MapEnv(HashMap::from([("ENV".to_string(), "prod".to_string())]));
+        let resolver = resolver().with_source(secrets);
+        // not parseable, the next source is tried
+        assert_eq!(resolver.resolve(), EnvType::Prod);
+
+        guard.set_var(KEY, "dev");
+        assert_eq!(resolver.resolve(), EnvType::Dev);
+    }
+
+    #[test]
+    fn test_source_key() {
+        struct AppEnvKey;
+        impl EnvKey for AppEnvKey {
+            fn key() -> &'static str {
+                "APP_ENV"
+            }
+        }
+
+        let secrets = MapEnv(HashMap::from([
+            ("ENV".to_string(), "prod".to_string()),
+            ("APP_ENV".to_string(), "ci".to_string()),
+        ]));
+        let env = EnvResolver::default()
+            .with_source_key::<_, AppEnvKey>(secrets)
+            .resolve();
+        assert_eq!(env, EnvType::Ci);
+    }
+
+    #[test]
+    fn test_default() {
+        assert_eq!(EnvResolver::default().resolve(), EnvType::Dev);
+        assert_eq!(
+            EnvResolver::default().with_default(EnvType::Prod).resolve(),
+            EnvType::Prod
+        );
+    }
+}
@@ -0,0 +1,315 @@
+//! This is used to keep sensitive context values, like passwords and API tokens, out of logs.
+//! Secret prints "[REDACTED]" with Debug and Display, so the describe and snapshot functions redact it too.
+//!
+//! # Example
+//!
+//! ```
+//! use env_type::context::ContextBuilder;
+//! use env_type::secret::{Secret, SecretStringContext};
+//! use env_type::types::EnvType;
+//!
+//! let context = ContextBuilder::<SecretStringContext>::default()
+//!     .with_value(EnvType::Prod, Secret::new("s3cr3t".to_string()))
+//!     .build();
+//!
+//! let password = context.get_for_env(&EnvType::Prod).unwrap();
+//! assert_eq!("[REDACTED]", format!("{:?}", password));
+//! assert_eq!("s3cr3t", password.expose());
+//! ```
+use crate::context::ContextMarker;
+use crate::environment::EnvironmentBuilder;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+
+/// The text printed instead of the secret value.
+const REDACTED: &str = "[REDACTED]";
+
+/// Secret is a wrapper of a sensitive value, the value is only read with expose.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    /// Wrap the sensitive value.
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Get the reference to the sensitive value.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Debug never shows the value.
+impl<T> std::fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// Display never shows the value.
+impl<T> std::fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// Secret is serialized as "[REDACTED]", so serialized diagnostics never show the value.
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for Secret<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+/// SecretStringContext is a context of a secret string for each environment, e.g. a database password.
+/// Declare a marker like this for each secret, when more than one is needed.
+pub struct SecretStringContext;
+
+impl ContextMarker for SecretStringContext {
+    type Value = Secret<String>;
+}
+
+/// SecretSource is the lookup of a secret by its key, e.g. the Shuttle secret store.
+/// The string maps are secret sources too, so a store can be replaced by a map or a fake in tests.
+pub trait SecretSource {
+    /// Get the secret for the key, None if the secret is missing.
+    fn secret(&self, key: &str) -> Option<String>;
+}
+
+impl<H: BuildHasher> SecretSource for HashMap<String, String, H> {
+    fn secret(&self, key: &str) -> Option<String> {
+        self.get(key).cloned()
+    }
+}
+
+impl SecretSource for BTreeMap<String, String> {
+    fn secret(&self, key: &str) -> Option<String> {
+        self.get(key).cloned()
+    }
+}
+
+/// SecretMapping maps a secret key to the context it is loaded into.
+/// The context type is captured when the mapping is created,
+/// because a context cannot be created from a TypeId alone.
+#[derive(Clone, Copy)]
+pub struct SecretMapping<'a> {
+    secret_key: &'a str,
+    register: fn(EnvironmentBuilder, &dyn SecretSource, &str) -> EnvironmentBuilder,
+}
+
+impl<'a> SecretMapping<'a> {
+    /// Create the mapping from the secret key to the context for the context marker.
+    pub fn new<M>(secret_key: &'a str) -> Self
+    where
+        M: ContextMarker<Value = String>,
+    {
+        Self {
+            secret_key,
+            register: |builder, store, secret_key| builder.with_secret::<M, _>(store, secret_key),
+        }
+    }
+}
+
+impl EnvironmentBuilder {
+    /// contexts_from_secrets is a function that loads a set of contexts from the secret source in one call.
+    /// Each mapping is loaded the same way as with_secret, for the current environment.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::context::ContextMarker;
+    /// use env_type::environment::EnvironmentBuilder;
+    /// use env_type::secret::SecretMapping;
+    /// use env_type::types::EnvType;
+    /// use std::collections::BTreeMap;
+    ///
+    /// struct DatabaseUrl;
+    ///
+    /// impl ContextMarker for DatabaseUrl {
+    ///  type Value = String;
+    /// }
+    ///
+    /// struct ApiKey;
+    ///
+    /// impl ContextMarker for ApiKey {
+    ///  type Value = String;
+    /// }
+    ///
+    /// let secrets = BTreeMap::from([
+    ///     ("DATABASE_URL".to_string(), "postgres://prod".to_string()),
+    ///     ("API_KEY".to_string(), "s3cr3t".to_string()),
+    /// ]);
+    /// let env = EnvironmentBuilder::default()
+    ///     .current_env(EnvType::Prod)
+    ///     .contexts_from_secrets(
+    ///         &secrets,
+    ///         &[
+    ///             SecretMapping::new::<DatabaseUrl>("DATABASE_URL"),
+    ///             SecretMapping::new::<ApiKey>("API_KEY"),
+    ///         ],
+    ///     )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(Some("s3cr3t".to_string()), env.current_value::<ApiKey>());
+    /// ```
+    pub fn contexts_from_secrets<S: SecretSource>(
+        self,
+        store: &S,
+        mapping: &[SecretMapping],
+    ) -> Self {
+        mapping.iter().fold(self, |builder, mapping| {
+            (mapping.register)(builder, store, mapping.secret_key)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::ContextBuilder;
+    use crate::environment::EnvironmentBuilder;
+    use crate::types::EnvType;
+
+    #[test]
+    fn test_redacted() {
+        let secret = Secret::new("hunter2".to_string());
+        assert_eq!(format!("{:?}", secret), "[REDACTED]");
+        assert_eq!(format!("{:#?}", secret), "[REDACTED]");
+        assert_eq!(secret.to_string(), "[REDACTED]");
+        assert!(!format!("{:?}", Some(&secret)).contains("hunter2"));
+        assert_eq!(secret.expose(), "hunter2");
+    }
+
+    #[test]
+    fn test_secret_source() {
+        let secrets = BTreeMap::from([("API_KEY".to_string(), "hunter2".to_string())]);
+        assert_eq!(secrets.secret("API_KEY"), Some("hunter2".to_string()));
+        assert_eq!(secrets.secret("MISSING"), None);
+
+        let secrets: HashMap<String, String> = secrets.into_iter().collect();
+        assert_eq!(secrets.secret("API_KEY"), Some("hunter2".to_string()));
+    }
+
+    /// A fake secret store, recording the looked up keys.
+    struct FakeSecretStore {
+        secrets: BTreeMap<String, String>,
+        lookups: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl SecretSource for FakeSecretStore {
+        fn secret(&self, key: &str) -> Option<String> {
+            self.lookups.lock().unwrap().push(key.to_string());
+            self.secrets.secret(key)
+        }
+    }
+
+    #[test]
+    fn test_contexts_from_secrets() {
+        struct DatabaseUrl;
+        impl ContextMarker for DatabaseUrl {
+            type Value = String;
+        }
+
+        struct ApiKey;
+        impl ContextMarker for ApiKey {
+            type Value = String;
+        }
+
+        let store = FakeSecretStore {
+            secrets: BTreeMap::from([("DATABASE_URL".to_string(), "postgres://prod".to_string())]),
+            lookups: Default::default(),
+        };
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .contexts_from_secrets(
+                &store,
+                &[
+                    SecretMapping::new::<DatabaseUrl>("DATABASE_URL"),
+                    SecretMapping::new::<ApiKey>("API_KEY"),
+                ],
+            )
+            .build()
+            .unwrap();
+        assert_eq!(
+            Some("postgres://prod".to_string()),
+            env.current_value::<DatabaseUrl>()
+        );
+        // a missing secret registers no context
+        assert!(env.context::<ApiKey>().is_none());
+        assert_eq!(*store.lookups.lock().unwrap(), ["DATABASE_URL", "API_KEY"]);
+
+        // the current environment can be set after the secrets
+        let env = EnvironmentBuilder::default()
+            .contexts_from_secrets(&store, &[SecretMapping::new::<DatabaseUrl>("DATABASE_URL")])
+            .current_env(EnvType::Stg)
+            .build()
+            .unwrap();
+        assert_eq!(
+            Some("postgres://prod".to_string()),
+            env.value::<DatabaseUrl>(&EnvType::Stg)
+        );
+    }
+
+    #[test]
+    fn test_eq() {
+        assert_eq!(Secret::new("a"), Secret::from("a"));
+        assert_ne!(Secret::new("a"), Secret::new("b"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_to_json() {
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .with_context_serializable(
+                ContextBuilder::<SecretStringContext>::default()
+                    .with_value(EnvType::Prod, Secret::new("hunter2".to_string()))
+                    .build(),
+            )
+            .build()
+            .unwrap();
+        let json = env.to_json();
+        assert_eq!(
+            json["contexts"]["env_type::secret::SecretStringContext"]["current_value"],
+            "[REDACTED]"
+        );
+        assert!(!json.to_string().contains("hunter2"));
+    }
+
+    #[test]
+    fn test_describe_with_values() {
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .with_rendered_context(
+                ContextBuilder::<SecretStringContext>::default()
+                    .with_value(EnvType::Prod, Secret::new("hunter2".to_string()))
+                    .with_default(Secret::new("local".to_string()))
+                    .build(),
+            )
+            .build()
+            .unwrap();
+        let described = env.describe_with_values();
+        assert!(described.contains("Prod = [REDACTED]"), "{described}");
+        assert!(!described.contains("hunter2"));
+        assert!(!described.contains("local"));
+        assert_eq!(
+            env.debug_snapshot(),
+            vec![(
+                "env_type::secret::SecretStringContext".to_string(),
+                "[REDACTED]".to_string()
+            )]
+        );
+        assert_eq!(
+            env.current_value::<SecretStringContext>().unwrap().expose(),
+            "hunter2"
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::secret::SecretSource;
 use crate::types::*;
 use shuttle_runtime::SecretStore;
 
@@ -28,3 +29,94 @@ impl AsEnvStr for SecretStore {
         self.get(T::key()).unwrap_or_default()
     }
 }
+
+/// AsEnvStrByKey is an implementation of the AsEnvStrByKey trait.
+/// This is used to get the environment type from the secret store with a prefixed key.
+impl AsEnvStrByKey for SecretStore {
+    fn as_env_str_by_key(&self, key: &str) -> String {
+        self.get(key).unwrap_or_default()
+    }
+}
+
+/// SecretSource is an implementation of the SecretSource trait.
+/// This is used to load the contexts from the secret store, see EnvironmentBuilder::with_secret.
+impl SecretSource for SecretStore {
+    fn secret(&self, key: &str) -> Option<String> {
+        self.get(key)
+    }
+}
+
+/// SecretMapping is re-exported from the secret module, it loads from any SecretSource.
+pub use crate::secret::SecretMapping;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::ContextMarker;
+    use crate::environment::EnvironmentBuilder;
+    use shuttle_runtime::Secret;
+    use std::collections::BTreeMap;
+
+    struct DatabaseUrl;
+    impl ContextMarker for DatabaseUrl {
+        type Value = String;
+    }
+
+    struct ApiKey;
+    impl ContextMarker for ApiKey {
+        type Value = String;
+    }
+
+    #[test]
+    fn test_from_env_types_prefixed() {
+        let store = SecretStore::new(BTreeMap::from([(
+            "MYAPP_ENV".to_string(),
+            Secret::new("test".to_string()),
+        )]));
+        assert_eq!(
+            EnvType::from_env_types_prefixed::<SecretStore, EnvType>(store, "MYAPP_"),
+            EnvType::Test
+        );
+    }
+
+    #[test]
+    fn test_secret_source() {
+        let store = SecretStore::new(BTreeMap::from([(
+            "DATABASE_URL".to_string(),
+            Secret::new("postgres://prod".to_string()),
+        )]));
+        assert_eq!(
+            store.secret("DATABASE_URL"),
+            Some("postgres://prod".to_string())
+        );
+        assert_eq!(store.secret("MISSING"), None);
+    }
+
+    #[test]
+    fn test_contexts_from_secrets() {
+        let store = SecretStore::new(BTreeMap::from([
+            (
+                "DATABASE_URL".to_string(),
+                Secret::new("postgres://prod".to_string()),
+            ),
+            ("API_KEY".to_string(), Secret::new("secret".to_string())),
+        ]));
+
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .contexts_from_secrets(
+                &store,
+                &[
+                    SecretMapping::new::<DatabaseUrl>("DATABASE_URL"),
+                    SecretMapping::new::<ApiKey>("API_KEY"),
+                ],
+            )
+            .build()
+            .unwrap();
+        assert_eq!(
+            Some("postgres://prod".to_string()),
+            env.current_value::<DatabaseUrl>()
+        );
+        assert_eq!(Some("secret".to_string()), env.current_value::<ApiKey>());
+    }
+}
@@ -0,0 +1,149 @@
+//! This is used to scope the environment to a tokio task,
+//! e.g. to run the same handler once per environment type concurrently in an integration test.
+//! The accessors fall back to the global environment (Environment::init_global) outside of a scope.
+//!
+//! # Example
+//!
+//! ```
+//! use env_type::environment::EnvironmentBuilder;
+//! use env_type::is_debug::debug_context;
+//! use env_type::task_local;
+//! use env_type::types::EnvType;
+//!
+//! # tokio::runtime::Runtime::new().unwrap().block_on(async {
+//! let env = EnvironmentBuilder::default()
+//!     .current_env(EnvType::Dev)
+//!     .with_context(debug_context().build())
+//!     .build()
+//!     .unwrap();
+//!
+//! task_local::scope(env, async {
+//!     assert_eq!(Some(EnvType::Dev), task_local::current_env());
+//!     assert!(task_local::is_debug());
+//! })
+//! .await;
+//! # });
+//! ```
+use crate::environment::Environment;
+use crate::is_debug::IsDebug;
+use crate::types::EnvType;
+use std::future::Future;
+use std::sync::Arc;
+
+tokio::task_local! {
+    static ENVIRONMENT: Arc<Environment>;
+}
+
+/// Run the future with the environment as the task-local environment.
+/// Scopes can be nested, the innermost one wins until its future completes.
+pub async fn scope<E, F>(env: E, f: F) -> F::Output
+where
+    E: Into<Arc<Environment>>,
+    F: Future,
+{
+    ENVIRONMENT.scope(env.into(), f).await
+}
+
+/// Get the task-local environment, or None outside of a scope.
+/// The global environment is not consulted, use with_current for the fallback.
+pub fn try_current() -> Option<Arc<Environment>> {
+    ENVIRONMENT.try_with(Arc::clone).ok()
+}
+
+/// Call the function with the task-local environment, or the global environment outside of a scope.
+/// Returns None if neither is available.
+///
+/// # Example
+///
+/// ```
+/// use env_type::environment::EnvironmentBuilder;
+/// use env_type::task_local;
+/// use env_type::types::EnvType;
+///
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let env = EnvironmentBuilder::default()
+///     .current_env(EnvType::Stg)
+///     .build()
+///     .unwrap();
+///
+/// let name = task_local::scope(env, async {
+///     task_local::with_current(|env| env.current_env().canonical_str())
+/// })
+/// .await;
+/// assert_eq!(Some("staging"), name);
+/// # });
+/// ```
+pub fn with_current<R, F>(f: F) -> Option<R>
+where
+    F: FnOnce(&Environment) -> R,
+{
+    match try_current() {
+        Some(env) => Some(f(&env)),
+        None => Environment::try_global().map(f),
+    }
+}
+
+/// Get the current environment type of the task-local environment, or the global environment outside of a scope.
+pub fn current_env() -> Option<EnvType> {
+    with_current(|env| env.current_env())
+}
+
+/// Check if the task-local environment, or the global environment outside of a scope, is a debug environment.
+/// Returns false if neither is available.
+pub fn is_debug() -> bool {
+    with_current(|env| env.is_debug()).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::EnvironmentBuilder;
+    use crate::is_debug::debug_context;
+    use std::time::Duration;
+
+    fn environment(current: EnvType) -> Environment {
+        EnvironmentBuilder::default()
+            .current_env(current)
+            .with_context(debug_context().build())
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_nested_scope() {
+        assert!(try_current().is_none());
+
+        scope(environment(EnvType::Dev), async {
+            assert_eq!(current_env(), Some(EnvType::Dev));
+            assert!(is_debug());
+
+            scope(environment(EnvType::Prod), async {
+                assert_eq!(current_env(), Some(EnvType::Prod));
+                assert!(!is_debug());
+            })
+            .await;
+
+            assert_eq!(current_env(), Some(EnvType::Dev));
+            assert!(is_debug());
+        })
+        .await;
+
+        assert!(try_current().is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_concurrent_tasks() {
+        let tasks = [EnvType::Dev, EnvType::Prod].map(|current| {
+            tokio::spawn(scope(environment(current), async move {
+                for _ in 0..10 {
+                    assert_eq!(current_env(), Some(current));
+                    assert_eq!(is_debug(), current.is_dev());
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                }
+            }))
+        });
+        for task in tasks {
+            task.await.unwrap();
+        }
+    }
+}
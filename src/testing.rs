@@ -0,0 +1,115 @@
+//! This is used to override environment variables in tests safely.
+//! `std::env::set_var` is process-wide, so tests running in parallel can observe each other's values.
+//! ScopedEnv serializes the tests holding a guard, and restores the previous values on drop.
+//!
+//! # Example
+//!
+//! ```
+//! use env_type::testing::ScopedEnv;
+//! use env_type::types::EnvType;
+//!
+//! {
+//!     let mut guard = ScopedEnv::set("ENV", "prod");
+//!     assert_eq!(EnvType::Prod, EnvType::from_env());
+//!
+//!     guard.set_var("ENV", "stg");
+//!     assert_eq!(EnvType::Stg, EnvType::from_env());
+//! }
+//! ```
+use std::ffi::{OsStr, OsString};
+use std::sync::{Mutex, MutexGuard};
+
+/// Serialize the guards, so only one test overrides environment variables at a time.
+static ENV_GUARD: Mutex<()> = Mutex::new(());
+
+/// ScopedEnv is a guard that overrides environment variables, and restores them on drop.
+/// Only one guard exists at a time in the process, a second guard waits for the first one to drop,
+/// so use set_var and remove_var on the same guard to change more variables.
+pub struct ScopedEnv {
+    /// The original values of the changed variables, in the order of the first change
+    saved: Vec<(String, Option<OsString>)>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl ScopedEnv {
+    /// Set the variable until the guard is dropped.
+    pub fn set<V: AsRef<OsStr>>(key: &str, value: V) -> Self {
+        let mut guard = Self::lock();
+        guard.set_var(key, value);
+        guard
+    }
+
+    /// Remove the variable until the guard is dropped.
+    pub fn unset(key: &str) -> Self {
+        let mut guard = Self::lock();
+        guard.remove_var(key);
+        guard
+    }
+
+    /// Set the variable in the scope of the guard, the original value is restored on drop.
+    pub fn set_var<V: AsRef<OsStr>>(&mut self, key: &str, value: V) -> &mut Self {
+        self.save(key);
+        std::env::set_var(key, value);
+        self
+    }
+
+    /// Remove the variable in the scope of the guard, the original value is restored on drop.
+    pub fn remove_var(&mut self, key: &str) -> &mut Self {
+        self.save(key);
+        std::env::remove_var(key);
+        self
+    }
+
+    fn lock() -> Self {
+        Self {
+            saved: Vec::new(),
+            // a test panicking with a guard only poisons the lock, the variables are restored by drop
+            _lock: ENV_GUARD.lock().unwrap_or_else(|e| e.into_inner()),
+        }
+    }
+
+    fn save(&mut self, key: &str) {
+        if !self.saved.iter().any(|(saved, _)| saved == key) {
+            self.saved.push((key.to_string(), std::env::var_os(key)));
+        }
+    }
+}
+
+impl Drop for ScopedEnv {
+    fn drop(&mut self) {
+        for (key, value) in self.saved.drain(..).rev() {
+            match value {
+                Some(value) => std::env::set_var(&key, value),
+                None => std::env::remove_var(&key),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scoped_env() {
+        std::env::set_var("TEST_SCOPED_ENV", "original");
+        std::env::remove_var("TEST_SCOPED_UNSET_ENV");
+        {
+            let mut guard = ScopedEnv::set("TEST_SCOPED_ENV", "prod");
+            assert_eq!(std::env::var("TEST_SCOPED_ENV").unwrap(), "prod");
+
+            guard
+                .set_var("TEST_SCOPED_ENV", "stg")
+                .set_var("TEST_SCOPED_UNSET_ENV", "test");
+            assert_eq!(std::env::var("TEST_SCOPED_ENV").unwrap(), "stg");
+        }
+        assert_eq!(std::env::var("TEST_SCOPED_ENV").unwrap(), "original");
+        assert!(std::env::var_os("TEST_SCOPED_UNSET_ENV").is_none());
+
+        {
+            let _guard = ScopedEnv::unset("TEST_SCOPED_ENV");
+            assert!(std::env::var_os("TEST_SCOPED_ENV").is_none());
+        }
+        assert_eq!(std::env::var("TEST_SCOPED_ENV").unwrap(), "original");
+    }
+}
@@ -0,0 +1,210 @@
+//! This is used to set the tracing level filter from the environment type.
+//! The level filter follows EnvType::default_log_level, e.g. Prod is WARN and Dev is DEBUG.
+//!
+//! # Example
+//!
+//! ```
+//! use env_type::types::EnvType;
+//!
+//! if let Err(err) = env_type::tracing::init_tracing(EnvType::from_env()) {
+//!     eprintln!("tracing is already initialized: {err}");
+//! }
+//! ```
+use crate::environment::Environment;
+use crate::log_level::LogLevel;
+use crate::types::EnvType;
+use ::tracing::level_filters::LevelFilter;
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Trace => LevelFilter::TRACE,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Error => LevelFilter::ERROR,
+        }
+    }
+}
+
+/// level_filter is a function that returns the tracing level filter for the environment type.
+pub fn level_filter(env: EnvType) -> LevelFilter {
+    env.default_log_level().into()
+}
+
+/// init_tracing is a function that installs the fmt subscriber as the global default,
+/// with the level filter for the environment type.
+/// An error is returned instead of a panic, if a global subscriber is already set.
+pub fn init_tracing(env: EnvType) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    tracing_subscriber::fmt()
+        .with_max_level(level_filter(env))
+        .try_init()
+}
+
+impl Environment {
+    /// Get the filter directives for tracing_subscriber::EnvFilter.
+    /// An explicit non-empty `RUST_LOG` environment variable wins,
+    /// otherwise it is the log level for the current environment, see Environment::log_level.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::environment::EnvironmentBuilder;
+    /// use env_type::log_level::log_level_context;
+    /// use env_type::types::EnvType;
+    ///
+    /// let env = EnvironmentBuilder::default()
+    ///     .current_env(EnvType::Prod)
+    ///     .with_context(log_level_context().build())
+    ///     .build()
+    ///     .unwrap();
+    /// std::env::remove_var("RUST_LOG");
+    /// assert_eq!("warn", env.env_filter());
+    /// ```
+    pub fn env_filter(&self) -> String {
+        match std::env::var("RUST_LOG") {
+            Ok(directives) if !directives.trim().is_empty() => directives,
+            _ => self.log_level().as_str().to_string(),
+        }
+    }
+
+    /// Get the tracing level filter for the current environment, see Environment::log_level.
+    pub fn level_filter(&self) -> LevelFilter {
+        self.log_level().into()
+    }
+
+    /// Run the closure in a span tagged with the name and the current environment.
+    /// The span is named "env_type", with the fields `name` and `env` (the canonical name).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::environment::EnvironmentBuilder;
+    /// use env_type::types::EnvType;
+    ///
+    /// let env = EnvironmentBuilder::default()
+    ///     .current_env(EnvType::Prod)
+    ///     .build()
+    ///     .unwrap();
+    /// let sum = env.instrument("sum", || 1 + 2);
+    /// assert_eq!(3, sum);
+    /// ```
+    pub fn instrument<T>(&self, name: &str, f: impl FnOnce() -> T) -> T {
+        ::tracing::info_span!("env_type", name, env = self.current_env().canonical_str())
+            .in_scope(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::EnvironmentBuilder;
+    use crate::log_level::log_level_context;
+    use crate::testing::ScopedEnv;
+    use ::tracing::field::{Field, Visit};
+    use ::tracing::span::{Attributes, Id, Record};
+    use ::tracing::{Event, Metadata, Subscriber};
+    use std::sync::{Arc, Mutex};
+
+    /// Records the fields of the new spans.
+    #[derive(Clone, Default)]
+    struct SpanRecorder {
+        fields: Arc<Mutex<Vec<(String, String)>>>,
+    }
+
+    impl Visit for SpanRecorder {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.fields
+                .lock()
+                .unwrap()
+                .push((field.name().to_string(), format!("{value:?}")));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.fields
+                .lock()
+                .unwrap()
+                .push((field.name().to_string(), value.to_string()));
+        }
+    }
+
+    impl Subscriber for SpanRecorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            span.record(&mut self.clone());
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn test_instrument() {
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Prod)
+            .build()
+            .unwrap();
+        let recorder = SpanRecorder::default();
+        let result = ::tracing::subscriber::with_default(recorder.clone(), || {
+            env.instrument("migrate", || 42)
+        });
+        assert_eq!(result, 42);
+        assert_eq!(
+            *recorder.fields.lock().unwrap(),
+            vec![
+                ("name".to_string(), "migrate".to_string()),
+                ("env".to_string(), "production".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_level_filter() {
+        assert_eq!(level_filter(EnvType::Dev), LevelFilter::DEBUG);
+        assert_eq!(level_filter(EnvType::Test), LevelFilter::INFO);
+        assert_eq!(level_filter(EnvType::Stg), LevelFilter::INFO);
+        assert_eq!(level_filter(EnvType::Prod), LevelFilter::WARN);
+        assert_eq!(level_filter(EnvType::Local), LevelFilter::DEBUG);
+        assert_eq!(level_filter(EnvType::Ci), LevelFilter::INFO);
+        assert_eq!(LevelFilter::from(LogLevel::Trace), LevelFilter::TRACE);
+        assert_eq!(LevelFilter::from(LogLevel::Error), LevelFilter::ERROR);
+    }
+
+    #[test]
+    fn test_env_filter() {
+        let environment = |current| {
+            EnvironmentBuilder::default()
+                .current_env(current)
+                .with_context(log_level_context().build())
+                .build()
+                .unwrap()
+        };
+
+        let mut guard = ScopedEnv::unset("RUST_LOG");
+        for (current, expected) in [
+            (EnvType::Dev, "debug"),
+            (EnvType::Test, "debug"),
+            (EnvType::Stg, "info"),
+            (EnvType::Prod, "warn"),
+        ] {
+            assert_eq!(environment(current).env_filter(), expected);
+        }
+        assert_eq!(environment(EnvType::Prod).level_filter(), LevelFilter::WARN);
+
+        guard.set_var("RUST_LOG", "my_app=trace,info");
+        assert_eq!(environment(EnvType::Prod).env_filter(), "my_app=trace,info");
+        guard.set_var("RUST_LOG", "");
+        assert_eq!(environment(EnvType::Prod).env_filter(), "warn");
+    }
+}
@@ -1,6 +1,12 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
 use std::str::FromStr;
+use std::sync::RwLock;
 use thiserror::Error;
 
+/// The current environment type resolved once by EnvType::current.
+static CURRENT_ENV: RwLock<Option<EnvType>> = RwLock::new(None);
+
 /// EnvType is an enum that represents the environment type.
 /// EnvType is derived from the strum crate, which provides the ability to convert the string to the enum.
 ///
@@ -15,8 +21,25 @@ use thiserror::Error;
 ///
 /// let custom_env = EnvType::Custom("Custom");
 /// assert_eq!(EnvType::Custom("Custom"), custom_env);
+///
+/// // EnvType is ordered in declaration order: Dev, Test, Stg, Prod, Local, Ci,
+/// // and custom environment types come last.
+/// assert!(EnvType::Dev < EnvType::Prod);
+/// assert!(EnvType::Prod < EnvType::Custom("Custom"));
 /// ```
-#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::EnumString, strum::EnumIs, Default, Hash)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    strum::EnumString,
+    strum::EnumIs,
+    Default,
+    Hash,
+)]
 #[strum(serialize_all = "PascalCase")]
 pub enum EnvType {
     #[default]
@@ -58,22 +81,134 @@ pub enum EnvType {
         serialize = "P"
     )]
     Prod,
+    #[strum(
+        serialize = "local",
+        serialize = "Local",
+        serialize = "LOCAL",
+        serialize = "l",
+        serialize = "L"
+    )]
+    Local,
+    #[strum(serialize = "ci", serialize = "Ci", serialize = "CI")]
+    Ci,
     Custom(&'static str),
 }
 
+/// EnvType is an implementation of the clap::ValueEnum trait.
+/// The canonical names are shown in the help output, and the other spellings accepted by FromStr are hidden aliases.
+///
+/// # Example
+///
+/// ```
+/// use clap::{Arg, Command};
+/// use env_type::types::EnvType;
+///
+/// let command = Command::new("app").arg(
+///     Arg::new("env")
+///         .long("env")
+///         .value_parser(clap::builder::EnumValueParser::<EnvType>::new()),
+/// );
+/// let matches = command.get_matches_from(["app", "--env", "stg"]);
+/// assert_eq!(Some(&EnvType::Stg), matches.get_one::<EnvType>("env"));
+/// ```
+#[cfg(feature = "clap")]
+impl clap::ValueEnum for EnvType {
+    fn value_variants<'a>() -> &'a [Self] {
+        Self::VARIANTS
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        let aliases: &[&str] = match self {
+            EnvType::Dev => &["Develop", "dev", "Dev", "DEV", "d", "D"],
+            EnvType::Test => &["Test", "TEST", "t", "T"],
+            EnvType::Stg => &["Staging", "stg", "Stg", "STG", "s", "S"],
+            EnvType::Prod => &["Production", "prod", "Prod", "PROD", "p", "P"],
+            EnvType::Local => &["Local", "LOCAL", "l", "L"],
+            EnvType::Ci => &["Ci", "CI"],
+            EnvType::Custom(_) => return None,
+        };
+        Some(
+            clap::builder::PossibleValue::new(self.canonical_str())
+                .aliases(aliases.iter().copied()),
+        )
+    }
+}
+
+/// EnvType is an implementation of the serde::Serialize trait.
+/// EnvType is serialized to the canonical name, e.g. "production".
+#[cfg(feature = "serde")]
+impl serde::Serialize for EnvType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.canonical_str())
+    }
+}
+
+/// EnvType is an implementation of the serde::Deserialize trait.
+/// EnvType is deserialized with the same spellings as FromStr, e.g. "prod", "p" and "production".
+/// An unknown string is an error, it is not defaulted to Dev.
+///
+/// # Example
+///
+/// ```
+/// use env_type::types::EnvType;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Config {
+///     env: EnvType,
+/// }
+///
+/// let config: Config = serde_json::from_str(r#"{"env": "p"}"#).unwrap();
+/// assert_eq!(EnvType::Prod, config.env);
+/// ```
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for EnvType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        EnvType::from_str(&value)
+            .map_err(|_| serde::de::Error::custom(EnvError::ParseError { value }))
+    }
+}
+
 /// EnvError is an enum that represents the environment error type.
-#[derive(Debug, Error)]
+/// EnvError implements Display and std::error::Error, so it can be used with `?` into `Box<dyn Error>`.
+#[derive(Debug, Clone, PartialEq, Error)]
 pub enum EnvError {
     #[error("No current environment specified")]
     NoCurrentEnv,
+    #[error("Environment variable not set: {key}")]
+    EnvVarNotSet { key: String },
+    #[error("Invalid environment value for {key}: {value}")]
+    InvalidEnvValue { key: String, value: String },
+    #[error("Failed to parse environment type: {value}")]
+    ParseError { value: String },
     #[error("Context not found for type")]
     ContextNotFound,
-    #[error("Context value not found for env")]
-    ContextValueNotFound,
+    #[error("Context value not found for {context} in {env:?}, tried: {tried:?}")]
+    ContextValueNotFound {
+        context: String,
+        env: EnvType,
+        tried: Vec<EnvType>,
+    },
+    #[error("File not found: {path}")]
+    FileNotFound { path: String },
     #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
     #[error("Provider error: {0}")]
     ProviderError(String),
+    #[error("Global environment is already initialized")]
+    GlobalAlreadyInitialized,
+    #[error("Missing values for environments: {0:?}")]
+    MissingEnvValues(Vec<EnvType>),
+    #[error("Non-canonical environment value: {input}")]
+    NonCanonical { input: String },
+    #[error("Missing required context: {name}")]
+    MissingRequiredContext { name: &'static str },
+    #[error("Key not found in {path}: {key}")]
+    KeyNotFound { path: String, key: String },
+    #[error("Unknown environment section in {path}: {section}")]
+    UnknownSection { path: String, section: String },
+    #[error("Type mismatch for {key}: {message}")]
+    TypeMismatch { key: String, message: String },
 }
 
 /// EnvKey is a trait that represents the environment key.
@@ -100,6 +235,27 @@ pub trait EnvKey {
     fn key() -> &'static str;
 }
 
+/// Derive macro for the EnvKey trait, enabled by the `derive` feature.
+///
+/// # Example
+///
+/// ```
+/// use env_type::types::{EnvKey, EnvType};
+///
+/// #[derive(EnvKey)]
+/// #[env_key("PAYMENTS_SERVICE_ENV")]
+/// struct PaymentsEnv;
+///
+/// // the key defaults to the SCREAMING_SNAKE_CASE of the type name
+/// #[derive(EnvKey)]
+/// struct BillingEnv;
+///
+/// assert_eq!("PAYMENTS_SERVICE_ENV", PaymentsEnv::key());
+/// assert_eq!("BILLING_ENV", BillingEnv::key());
+/// ```
+#[cfg(feature = "derive")]
+pub use env_type_derive::EnvKey;
+
 /// EnvType is an implementation of the EnvKey trait.
 /// The default environment key is "ENV".
 impl EnvKey for EnvType {
@@ -146,6 +302,178 @@ impl AsEnvStr for EnvType {
     }
 }
 
+/// AsEnvStrByKey is a trait like AsEnvStr, with the key given at runtime.
+/// This is used when the key is not known statically, e.g. a namespaced key with a prefix.
+pub trait AsEnvStrByKey {
+    fn as_env_str_by_key(&self, key: &str) -> String;
+}
+
+/// EnvType is an implementation of the AsEnvStrByKey trait.
+/// EnvType is based on env var.
+impl AsEnvStrByKey for EnvType {
+    fn as_env_str_by_key(&self, key: &str) -> String {
+        std::env::var(key).unwrap_or_default()
+    }
+}
+
+/// MapEnv is a map-backed source of environment values, e.g. for deterministic tests.
+/// The value is looked up by the environment key, like the process environment variables.
+///
+/// # Example
+///
+/// ```
+/// use env_type::types::{EnvType, MapEnv};
+/// use std::collections::HashMap;
+///
+/// let map = MapEnv(HashMap::from([("ENV".to_string(), "prod".to_string())]));
+/// assert_eq!(EnvType::Prod, EnvType::from_env_types::<MapEnv, EnvType>(map));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MapEnv(pub HashMap<String, String>);
+
+/// MapEnv is an implementation of the AsEnvStr trait.
+/// The value is empty if the key is not in the map.
+impl AsEnvStr for MapEnv {
+    fn as_env_str<T: EnvKey>(&self) -> String {
+        self.0.get(T::key()).cloned().unwrap_or_default()
+    }
+}
+
+/// HashMap is an implementation of the AsEnvStr trait, for settings already loaded into a map.
+/// The key is looked up with the exact casing, like the process environment variables,
+/// and the lookup borrows the key, so nothing is allocated if the key is missing.
+///
+/// # Example
+///
+/// ```
+/// use env_type::types::EnvType;
+/// use std::collections::HashMap;
+///
+/// let map = HashMap::from([("ENV".to_string(), "prod".to_string())]);
+/// assert_eq!(EnvType::Prod, EnvType::from_env_types::<HashMap<String, String>, EnvType>(map));
+/// ```
+impl<H: BuildHasher> AsEnvStr for HashMap<String, String, H> {
+    fn as_env_str<T: EnvKey>(&self) -> String {
+        self.as_env_str_by_key(T::key())
+    }
+}
+
+/// HashMap is an implementation of the AsEnvStrByKey trait.
+impl<H: BuildHasher> AsEnvStrByKey for HashMap<String, String, H> {
+    fn as_env_str_by_key(&self, key: &str) -> String {
+        self.get(key).cloned().unwrap_or_default()
+    }
+}
+
+/// HashMap with borrowed keys is an implementation of the AsEnvStr trait.
+///
+/// # Example
+///
+/// ```
+/// use env_type::types::EnvType;
+/// use std::collections::HashMap;
+///
+/// let map = HashMap::from([("ENV", "stg".to_string())]);
+/// assert_eq!(EnvType::Stg, EnvType::from_env_types::<_, EnvType>(map));
+/// ```
+impl<H: BuildHasher> AsEnvStr for HashMap<&str, String, H> {
+    fn as_env_str<T: EnvKey>(&self) -> String {
+        self.as_env_str_by_key(T::key())
+    }
+}
+
+/// HashMap with borrowed keys is an implementation of the AsEnvStrByKey trait.
+impl<H: BuildHasher> AsEnvStrByKey for HashMap<&str, String, H> {
+    fn as_env_str_by_key(&self, key: &str) -> String {
+        self.get(key).cloned().unwrap_or_default()
+    }
+}
+
+/// BTreeMap is an implementation of the AsEnvStr trait.
+///
+/// # Example
+///
+/// ```
+/// use env_type::types::EnvType;
+/// use std::collections::BTreeMap;
+///
+/// let map = BTreeMap::from([("ENV".to_string(), "test".to_string())]);
+/// assert_eq!(EnvType::Test, EnvType::from_env_types::<_, EnvType>(map));
+/// ```
+impl AsEnvStr for BTreeMap<String, String> {
+    fn as_env_str<T: EnvKey>(&self) -> String {
+        self.as_env_str_by_key(T::key())
+    }
+}
+
+/// BTreeMap is an implementation of the AsEnvStrByKey trait.
+impl AsEnvStrByKey for BTreeMap<String, String> {
+    fn as_env_str_by_key(&self, key: &str) -> String {
+        self.get(key).cloned().unwrap_or_default()
+    }
+}
+
+/// A slice of key-value pairs is an implementation of the AsEnvStr trait, e.g. for a parsed argument list.
+/// The first pair with the key is used.
+///
+/// # Example
+///
+/// ```
+/// use env_type::types::EnvType;
+///
+/// let pairs = vec![("ENV".to_string(), "ci".to_string())];
+/// assert_eq!(EnvType::Ci, EnvType::from_env_types::<&[_], EnvType>(&pairs));
+/// ```
+impl AsEnvStr for &[(String, String)] {
+    fn as_env_str<T: EnvKey>(&self) -> String {
+        self.as_env_str_by_key(T::key())
+    }
+}
+
+/// A slice of key-value pairs is an implementation of the AsEnvStrByKey trait.
+impl AsEnvStrByKey for &[(String, String)] {
+    fn as_env_str_by_key(&self, key: &str) -> String {
+        self.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// AsyncEnvStr is the async version of AsEnvStr, for sources like HTTP secrets backends.
+///
+/// # Example
+///
+/// ```
+/// use env_type::types::{AsyncEnvStr, EnvKey, EnvType};
+///
+/// struct SecretsBackend;
+///
+/// #[async_trait::async_trait]
+/// impl AsyncEnvStr for SecretsBackend {
+///     async fn as_env_str<T: EnvKey>(&self) -> String {
+///         // e.g. fetch T::key() from the backend
+///         "prod".to_string()
+///     }
+/// }
+///
+/// async fn resolve() -> EnvType {
+///     EnvType::from_async_env_types::<_, EnvType>(SecretsBackend).await
+/// }
+/// ```
+#[cfg(feature = "async")]
+#[async_trait::async_trait]
+pub trait AsyncEnvStr {
+    async fn as_env_str<T: EnvKey>(&self) -> String;
+}
+
+/// MapEnv is an implementation of the AsEnvStrByKey trait.
+impl AsEnvStrByKey for MapEnv {
+    fn as_env_str_by_key(&self, key: &str) -> String {
+        self.0.get(key).cloned().unwrap_or_default()
+    }
+}
+
 /// FromKey<V, S> is a trait like From<T> with a key.
 pub trait FromKey<V, S> {
     fn from_key<K: EnvKey>(value: V) -> S;
@@ -178,113 +506,784 @@ pub trait AsEnvTypeStr {
     fn as_env_type_str(&self) -> Option<String>;
 }
 
+/// EnvType is an implementation of the VariantArray trait.
+/// Custom environment types are not included, because they are not known in advance.
+impl strum::VariantArray for EnvType {
+    const VARIANTS: &'static [Self] = EnvType::VARIANTS;
+}
+
+/// TryFrom<u8> is an implementation to parse the environment type from an integer severity.
+/// The severity follows the promotion order, 0 => Dev, 1 => Test, 2 => Stg, 3 => Prod, and the others are errors.
+///
+/// # Example
+///
+/// ```
+/// use env_type::types::EnvType;
+///
+/// assert_eq!(EnvType::Prod, EnvType::try_from(3u8).unwrap());
+/// assert_eq!(Some(3), EnvType::Prod.ordinal());
+/// ```
+impl TryFrom<u8> for EnvType {
+    type Error = EnvError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::try_from(u32::from(value))
+    }
+}
+
+/// TryFrom<u32> is an implementation to parse the environment type from an integer, same as TryFrom<u8>.
+impl TryFrom<u32> for EnvType {
+    type Error = EnvError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(EnvType::Dev),
+            1 => Ok(EnvType::Test),
+            2 => Ok(EnvType::Stg),
+            3 => Ok(EnvType::Prod),
+            _ => Err(EnvError::ParseError {
+                value: value.to_string(),
+            }),
+        }
+    }
+}
+
 impl EnvType {
-    /// EnvType::from_env is a function that returns the environment type from the environment variable.
-    /// This is deligated to from_env_key with EnvType as default from env key.
-    /// The default environment type is Dev.
+    /// All known environment types in declaration order.
+    /// Dev, Test, Stg and Prod are in promotion order, Local and Ci are appended to keep the existing order.
+    /// Custom environment types are not included.
+    pub const VARIANTS: &'static [EnvType] = &[
+        EnvType::Dev,
+        EnvType::Test,
+        EnvType::Stg,
+        EnvType::Prod,
+        EnvType::Local,
+        EnvType::Ci,
+    ];
+
+    /// EnvType::canonical_str is a function that returns the canonical name of the environment type.
+    /// The canonical name is the long lowercase name, which is parsed back to the same environment type.
     ///
     /// # Example
     ///
     /// ```
     /// use env_type::types::EnvType;
-    /// use std::str::FromStr;
     ///
-    /// std::env::set_var("ENV", "Production");
-    /// let env = EnvType::from_env();
-    /// assert_eq!(EnvType::Prod, env);
+    /// assert_eq!("production", EnvType::Prod.canonical_str());
+    /// assert_eq!("Custom", EnvType::Custom("Custom").canonical_str());
     /// ```
-    pub fn from_env() -> Self {
-        Self::from_env_types::<Self, Self>(Self::default())
+    pub fn canonical_str(&self) -> &'static str {
+        match self {
+            EnvType::Dev => "develop",
+            EnvType::Test => "test",
+            EnvType::Stg => "staging",
+            EnvType::Prod => "production",
+            EnvType::Local => "local",
+            EnvType::Ci => "ci",
+            EnvType::Custom(name) => name,
+        }
     }
 
-    /// EnvType::from_env_key is a function that returns the environment type from the environment variable.
-    /// The default environment type is Dev.
+    /// EnvType::short_str is a function that returns the short code of the environment type.
+    /// The short code is the one-letter alias parsed by FromStr, "ci" for Ci,
+    /// and the inner name for a custom environment type.
     ///
     /// # Example
     ///
     /// ```
     /// use env_type::types::EnvType;
-    /// use std::str::FromStr;
     ///
-    /// std::env::set_var("ENV", "Test");
-    /// let env = EnvType::from_env_key::<EnvType>();
-    /// assert_eq!(EnvType::Test, env);
+    /// assert_eq!("p", EnvType::Prod.short_str());
     /// ```
-    pub fn from_env_key<K: EnvKey>() -> Self {
-        Self::from_env_types::<Self, K>(Self::default())
+    pub fn short_str(&self) -> &'static str {
+        match self {
+            EnvType::Dev => "d",
+            EnvType::Test => "t",
+            EnvType::Stg => "s",
+            EnvType::Prod => "p",
+            EnvType::Local => "l",
+            EnvType::Ci => "ci",
+            EnvType::Custom(name) => name,
+        }
     }
 
-    /// EnvType::from_env_types is a function that returns the EnvType from AsEnvStr and EnvKey.
-    pub fn from_env_types<S: AsEnvStr, K: EnvKey>(s: S) -> Self {
-        Self::from_str(&s.as_env_str::<K>()).unwrap_or_default()
+    /// EnvType::verbose_label is a function that returns the canonical name with the short code,
+    /// e.g. "production(p)", for log formats.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::EnvType;
+    ///
+    /// assert_eq!("production(p)", EnvType::Prod.verbose_label());
+    /// ```
+    pub fn verbose_label(&self) -> String {
+        format!("{}({})", self.canonical_str(), self.short_str())
     }
 
-    /// EnvType::from_env_str is a function that returns the environment type from the string.
-    /// The default environment type is Dev.
+    /// EnvType::ordinal is a function that returns the integer severity, the inverse of TryFrom<u8>.
+    /// Only Dev, Test, Stg and Prod have a severity, the others are None.
+    pub fn ordinal(&self) -> Option<u8> {
+        match self {
+            EnvType::Dev => Some(0),
+            EnvType::Test => Some(1),
+            EnvType::Stg => Some(2),
+            EnvType::Prod => Some(3),
+            EnvType::Local | EnvType::Ci | EnvType::Custom(_) => None,
+        }
+    }
+
+    /// EnvType::canonical_names is a function that returns the canonical names of Dev, Test, Stg and Prod
+    /// in promotion order, e.g. for dropdowns and validation lists.
     ///
     /// # Example
     ///
     /// ```
-    /// use env_type::types::{EnvType, AsEnvTypeStr};
-    /// use std::str::FromStr;
+    /// use env_type::types::EnvType;
     ///
-    /// struct Config {
-    ///   env_str: String,
-    /// }
-    /// let config = Config {
-    ///  env_str: "Production".to_string(),
-    /// };
+    /// assert_eq!(
+    ///     ["develop", "test", "staging", "production"],
+    ///     EnvType::canonical_names()
+    /// );
+    /// ```
+    pub fn canonical_names() -> [&'static str; 4] {
+        ["develop", "test", "staging", "production"]
+    }
+
+    /// EnvType::indexed_all is a function that returns all known environment types
+    /// with the index in VARIANTS and the canonical name.
     ///
-    /// impl AsEnvTypeStr for Config {
-    ///   fn as_env_type_str(&self) -> Option<String> {
-    ///     Some(self.env_str.clone())
-    ///   }
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::EnvType;
+    ///
+    /// for (index, env, name) in EnvType::indexed_all() {
+    ///     println!("{index}: {env:?} ({name})");
     /// }
-    /// let env = EnvType::from_env_str(config);
-    /// assert_eq!(EnvType::Prod, env);
-    pub fn from_env_str<T: AsEnvTypeStr>(t: T) -> Self {
-        Self::from_str(t.as_env_type_str().unwrap_or_default().as_str()).unwrap_or_default()
+    /// assert_eq!((3, EnvType::Prod, "production"), EnvType::indexed_all()[3]);
+    /// ```
+    pub fn indexed_all() -> [(u8, EnvType, &'static str); 6] {
+        let mut indexed = [(0, EnvType::Dev, ""); 6];
+        for (i, env) in Self::VARIANTS.iter().enumerate() {
+            indexed[i] = (i as u8, *env, env.canonical_str());
+        }
+        indexed
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::str::FromStr;
 
-    #[test]
-    fn test_env_type() {
-        assert_eq!(EnvType::from_str("Develop").unwrap(), EnvType::Dev);
-        assert_eq!(EnvType::from_str("develop").unwrap(), EnvType::Dev);
-        assert_eq!(EnvType::from_str("DEV").unwrap(), EnvType::Dev);
-        assert_eq!(EnvType::from_str("Dev").unwrap(), EnvType::Dev);
-        assert_eq!(EnvType::from_str("dev").unwrap(), EnvType::Dev);
-        assert_eq!(EnvType::from_str("D").unwrap(), EnvType::Dev);
-        assert_eq!(EnvType::from_str("d").unwrap(), EnvType::Dev);
-        assert_eq!(EnvType::from_str("TEST").unwrap(), EnvType::Test);
-        assert_eq!(EnvType::from_str("Test").unwrap(), EnvType::Test);
-        assert_eq!(EnvType::from_str("test").unwrap(), EnvType::Test);
-        assert_eq!(EnvType::from_str("T").unwrap(), EnvType::Test);
-        assert_eq!(EnvType::from_str("t").unwrap(), EnvType::Test);
-        assert_eq!(EnvType::from_str("Staging").unwrap(), EnvType::Stg);
-        assert_eq!(EnvType::from_str("staging").unwrap(), EnvType::Stg);
-        assert_eq!(EnvType::from_str("STG").unwrap(), EnvType::Stg);
-        assert_eq!(EnvType::from_str("Stg").unwrap(), EnvType::Stg);
-        assert_eq!(EnvType::from_str("stg").unwrap(), EnvType::Stg);
-        assert_eq!(EnvType::from_str("S").unwrap(), EnvType::Stg);
-        assert_eq!(EnvType::from_str("s").unwrap(), EnvType::Stg);
-        assert_eq!(EnvType::from_str("Production").unwrap(), EnvType::Prod);
-        assert_eq!(EnvType::from_str("production").unwrap(), EnvType::Prod);
-        assert_eq!(EnvType::from_str("PROD").unwrap(), EnvType::Prod);
-        assert_eq!(EnvType::from_str("Prod").unwrap(), EnvType::Prod);
-        assert_eq!(EnvType::from_str("prod").unwrap(), EnvType::Prod);
-        assert_eq!(EnvType::from_str("P").unwrap(), EnvType::Prod);
-        assert_eq!(EnvType::from_str("p").unwrap(), EnvType::Prod);
+    /// EnvType::all is a function that returns all known environment types as an owned Vec,
+    /// in the order of VARIANTS.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::EnvType;
+    ///
+    /// let names: Vec<&str> = EnvType::all().into_iter().map(|env| env.canonical_str()).collect();
+    /// assert_eq!(names.len(), EnvType::VARIANTS.len());
+    /// ```
+    pub fn all() -> Vec<EnvType> {
+        Self::VARIANTS.to_vec()
     }
 
-    #[test]
-    fn test_is_debug() {
-        assert!(EnvType::Dev.is_dev());
+    /// EnvType::iter is a function that returns an iterator over all known environment types,
+    /// in the order of VARIANTS.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::EnvType;
+    ///
+    /// assert_eq!(Some(EnvType::Dev), EnvType::iter().next());
+    /// assert!(EnvType::iter().all(|env| !env.is_custom()));
+    /// ```
+    pub fn iter() -> impl Iterator<Item = EnvType> {
+        Self::VARIANTS.iter().copied()
+    }
+
+    /// EnvType::parse is a function that parses the environment type exactly like `FromStr`,
+    /// but the error keeps the input, so the message names what could not be parsed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::EnvType;
+    ///
+    /// assert_eq!(EnvType::Prod, EnvType::parse("prod").unwrap());
+    /// assert_eq!(
+    ///     "Failed to parse environment type: banana",
+    ///     EnvType::parse("banana").unwrap_err().to_string()
+    /// );
+    /// ```
+    pub fn parse(s: &str) -> Result<Self, EnvError> {
+        Self::from_str(s).map_err(|_| EnvError::ParseError {
+            value: s.to_string(),
+        })
+    }
+
+    /// EnvType::from_str_lenient is a function that parses the environment type leniently.
+    /// Surrounding ASCII whitespace is trimmed and the string is lowercased before matching,
+    /// so `" Production\n"` is parsed as Prod. Use `FromStr` for exact matching.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::EnvType;
+    ///
+    /// assert_eq!(EnvType::Prod, EnvType::from_str_lenient(" Production\n").unwrap());
+    /// assert!(EnvType::from_str_lenient("unknown").is_err());
+    /// ```
+    pub fn from_str_lenient(s: &str) -> Result<Self, EnvError> {
+        Self::from_str(&s.trim_ascii().to_ascii_lowercase()).map_err(|_| EnvError::ParseError {
+            value: s.to_string(),
+        })
+    }
+
+    /// EnvType::from_str_lossy is a function that parses the environment type without losing unknown strings.
+    /// If the string is a known environment type, the environment type and None are returned.
+    /// Otherwise the default environment type (Dev) and the original string are returned,
+    /// so preview environments like "pr-1234" can be logged or handled by the caller.
+    ///
+    /// The original string is not stored in EnvType, because EnvType is Copy.
+    /// To keep an unknown environment as EnvType, use `EnvType::Custom` with a `&'static str`.
+    /// Custom environment types are not included in `VARIANTS`, `is_custom()` is true for them,
+    /// and Context lookups for them fall through to the default value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::EnvType;
+    ///
+    /// assert_eq!((EnvType::Prod, None), EnvType::from_str_lossy("prod"));
+    /// assert_eq!(
+    ///     (EnvType::Dev, Some("pr-1234".to_string())),
+    ///     EnvType::from_str_lossy("pr-1234")
+    /// );
+    /// ```
+    pub fn from_str_lossy(s: &str) -> (Self, Option<String>) {
+        match Self::from_str(s) {
+            Ok(env) => (env, None),
+            Err(_) => (Self::default(), Some(s.to_string())),
+        }
+    }
+
+    /// EnvType::from_env is a function that returns the environment type from the environment variable.
+    /// This is deligated to from_env_key with EnvType as default from env key.
+    /// The default environment type is Dev.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::EnvType;
+    /// use std::str::FromStr;
+    ///
+    /// std::env::set_var("ENV", "Production");
+    /// let env = EnvType::from_env();
+    /// assert_eq!(EnvType::Prod, env);
+    /// ```
+    pub fn from_env() -> Self {
+        Self::from_env_types::<Self, Self>(Self::default())
+    }
+
+    /// EnvType::from_env_or is a version of from_env with the given fallback instead of Dev,
+    /// e.g. Prod to fail closed when the environment variable is missing.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::EnvType;
+    ///
+    /// std::env::remove_var("ENV");
+    /// assert_eq!(EnvType::Prod, EnvType::from_env_or(EnvType::Prod));
+    /// ```
+    pub fn from_env_or(default: EnvType) -> Self {
+        Self::from_env_key_or::<Self>(default)
+    }
+
+    /// EnvType::from_env_key_or is a version of from_env_key with the given fallback instead of Dev.
+    pub fn from_env_key_or<K: EnvKey>(default: EnvType) -> Self {
+        Self::from_str(&default.as_env_str::<K>()).unwrap_or(default)
+    }
+
+    /// EnvType::current is a function that returns the environment type resolved once from the environment variable.
+    /// The first call resolves with from_env and caches the result, later calls return the cached value.
+    /// Changes of the environment variable after the first call are not observed until reset_current is called.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::EnvType;
+    ///
+    /// std::env::set_var("ENV", "Production");
+    /// assert_eq!(EnvType::Prod, EnvType::current());
+    ///
+    /// std::env::set_var("ENV", "Test");
+    /// assert_eq!(EnvType::Prod, EnvType::current());
+    /// ```
+    pub fn current() -> Self {
+        if let Some(env) = *CURRENT_ENV.read().unwrap_or_else(|e| e.into_inner()) {
+            return env;
+        }
+        let mut current = CURRENT_ENV.write().unwrap_or_else(|e| e.into_inner());
+        *current.get_or_insert_with(Self::from_env)
+    }
+
+    /// EnvType::reset_current is a function that clears the cached environment type,
+    /// so the next EnvType::current call resolves from the environment variable again.
+    /// This is mainly for tests.
+    pub fn reset_current() {
+        *CURRENT_ENV.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// EnvType::from_env_key is a function that returns the environment type from the environment variable.
+    /// The default environment type is Dev.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::EnvType;
+    /// use std::str::FromStr;
+    ///
+    /// std::env::set_var("ENV", "Test");
+    /// let env = EnvType::from_env_key::<EnvType>();
+    /// assert_eq!(EnvType::Test, env);
+    /// ```
+    pub fn from_env_key<K: EnvKey>() -> Self {
+        Self::from_env_types::<Self, K>(Self::default())
+    }
+
+    /// EnvType::from_node_env is a function that returns the environment type from `NODE_ENV`,
+    /// for deployments sharing the Node.js convention.
+    /// "production" is Prod, "development" is Dev, and "test" is Test.
+    /// The default environment type is Dev, if the variable is not set or has another value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::EnvType;
+    ///
+    /// std::env::set_var("NODE_ENV", "production");
+    /// assert_eq!(EnvType::Prod, EnvType::from_node_env());
+    /// ```
+    pub fn from_node_env() -> Self {
+        match std::env::var("NODE_ENV").as_deref() {
+            Ok("production") => EnvType::Prod,
+            Ok("test") => EnvType::Test,
+            Ok("development") => EnvType::Dev,
+            _ => Self::default(),
+        }
+    }
+
+    /// EnvType::from_env_os is a version of from_env_key that tolerates non-UTF-8 values.
+    /// The value is read with `var_os` and converted lossily,
+    /// and the invalid bytes at both ends are trimmed before parsing, e.g. "prod\xFF" is Prod.
+    /// The default environment type is Dev, if the value is not set or still not parseable.
+    pub fn from_env_os<K: EnvKey>() -> Self {
+        std::env::var_os(K::key())
+            .and_then(|value| {
+                let value = value.to_string_lossy();
+                let value = value
+                    .trim_matches(|c: char| c == char::REPLACEMENT_CHARACTER || c.is_whitespace());
+                Self::from_str(value).ok()
+            })
+            .unwrap_or_default()
+    }
+
+    /// EnvType::try_from_env is a strict version of from_env.
+    /// It returns an error instead of falling back to the default environment type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::EnvType;
+    ///
+    /// std::env::set_var("ENV", "Production");
+    /// let env = EnvType::try_from_env();
+    /// assert_eq!(EnvType::Prod, env.unwrap());
+    /// ```
+    pub fn try_from_env() -> Result<Self, EnvError> {
+        Self::try_from_env_key::<Self>()
+    }
+
+    /// EnvType::try_from_env_key is a strict version of from_env_key.
+    /// It returns EnvError::EnvVarNotSet if the variable is not set,
+    /// and EnvError::InvalidEnvValue if the value is not a recognized environment type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::{EnvError, EnvKey, EnvType};
+    ///
+    /// struct StrictEnvKey;
+    ///
+    /// impl EnvKey for StrictEnvKey {
+    ///     fn key() -> &'static str {
+    ///         "STRICT_ENV"
+    ///     }
+    /// }
+    ///
+    /// std::env::remove_var("STRICT_ENV");
+    /// assert!(matches!(
+    ///     EnvType::try_from_env_key::<StrictEnvKey>(),
+    ///     Err(EnvError::EnvVarNotSet { .. })
+    /// ));
+    ///
+    /// std::env::set_var("STRICT_ENV", "unknown");
+    /// assert!(matches!(
+    ///     EnvType::try_from_env_key::<StrictEnvKey>(),
+    ///     Err(EnvError::InvalidEnvValue { .. })
+    /// ));
+    /// ```
+    pub fn try_from_env_key<K: EnvKey>() -> Result<Self, EnvError> {
+        let key = K::key();
+        let value = Self::read_env_key(key)?;
+        Self::from_str(&value).map_err(|_| EnvError::InvalidEnvValue {
+            key: key.to_string(),
+            value,
+        })
+    }
+
+    /// EnvType::from_env_very_strict is a stricter version of try_from_env_key.
+    /// Only the canonical names (e.g. "develop", "production") are accepted.
+    /// A recognized alias (e.g. "prod") returns EnvError::NonCanonical,
+    /// and an unknown value returns EnvError::InvalidEnvValue.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::{EnvError, EnvKey, EnvType};
+    ///
+    /// struct VeryStrictEnvKey;
+    ///
+    /// impl EnvKey for VeryStrictEnvKey {
+    ///     fn key() -> &'static str {
+    ///         "VERY_STRICT_ENV"
+    ///     }
+    /// }
+    ///
+    /// std::env::set_var("VERY_STRICT_ENV", "production");
+    /// assert_eq!(EnvType::Prod, EnvType::from_env_very_strict::<VeryStrictEnvKey>().unwrap());
+    ///
+    /// std::env::set_var("VERY_STRICT_ENV", "prod");
+    /// assert!(matches!(
+    ///     EnvType::from_env_very_strict::<VeryStrictEnvKey>(),
+    ///     Err(EnvError::NonCanonical { .. })
+    /// ));
+    /// ```
+    pub fn from_env_very_strict<K: EnvKey>() -> Result<Self, EnvError> {
+        let key = K::key();
+        let value = Self::read_env_key(key)?;
+        match Self::from_str(&value) {
+            Ok(env) if env.canonical_str() == value => Ok(env),
+            Ok(_) => Err(EnvError::NonCanonical { input: value }),
+            Err(_) => Err(EnvError::InvalidEnvValue {
+                key: key.to_string(),
+                value,
+            }),
+        }
+    }
+
+    /// Read the environment variable for the strict constructors.
+    fn read_env_key(key: &str) -> Result<String, EnvError> {
+        std::env::var(key).map_err(|err| match err {
+            std::env::VarError::NotPresent => EnvError::EnvVarNotSet {
+                key: key.to_string(),
+            },
+            std::env::VarError::NotUnicode(value) => EnvError::InvalidEnvValue {
+                key: key.to_string(),
+                value: value.to_string_lossy().into_owned(),
+            },
+        })
+    }
+
+    /// EnvType::match_env_keys is a function that resolves the environment type from a chain of environment variables.
+    /// The keys are tried in order, and the first key that is set and parses wins.
+    /// A key that is set but not parseable is skipped.
+    /// The matched key is returned with the environment type, e.g. for logging at startup.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::EnvType;
+    ///
+    /// std::env::remove_var("MATCH_ENV");
+    /// std::env::set_var("MATCH_APP_ENV", "unknown");
+    /// std::env::set_var("MATCH_RUST_ENV", "stg");
+    ///
+    /// let matched = EnvType::match_env_keys(&["MATCH_ENV", "MATCH_APP_ENV", "MATCH_RUST_ENV"]);
+    /// assert_eq!(Some((EnvType::Stg, "MATCH_RUST_ENV")), matched);
+    /// ```
+    pub fn match_env_keys<'a>(keys: &[&'a str]) -> Option<(Self, &'a str)> {
+        keys.iter().find_map(|key| {
+            std::env::var(key)
+                .ok()
+                .and_then(|value| Self::from_str(&value).ok())
+                .map(|env| (env, *key))
+        })
+    }
+
+    /// EnvType::try_match_env_keys is a strict version of match_env_keys.
+    /// The keys are tried in order, and the first key that is set must parse,
+    /// otherwise EnvError::InvalidEnvValue is returned.
+    /// If none of the keys are set, EnvError::EnvVarNotSet is returned with all keys.
+    pub fn try_match_env_keys<'a>(keys: &[&'a str]) -> Result<(Self, &'a str), EnvError> {
+        for key in keys {
+            match std::env::var(key) {
+                Ok(value) => {
+                    return Self::from_str(&value).map(|env| (env, *key)).map_err(|_| {
+                        EnvError::InvalidEnvValue {
+                            key: key.to_string(),
+                            value,
+                        }
+                    })
+                }
+                Err(std::env::VarError::NotUnicode(value)) => {
+                    return Err(EnvError::InvalidEnvValue {
+                        key: key.to_string(),
+                        value: value.to_string_lossy().into_owned(),
+                    })
+                }
+                Err(std::env::VarError::NotPresent) => continue,
+            }
+        }
+        Err(EnvError::EnvVarNotSet {
+            key: keys.join(", "),
+        })
+    }
+
+    /// EnvType::from_env_keys is a function that returns the environment type from a chain of environment variables.
+    /// The first key that is set and parses wins, the order of keys is the precedence.
+    /// The default environment type is Dev.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::EnvType;
+    ///
+    /// std::env::set_var("KEYS_ENV", "Production");
+    /// std::env::set_var("KEYS_APP_ENV", "Test");
+    /// let env = EnvType::from_env_keys(&["KEYS_ENV", "KEYS_APP_ENV"]);
+    /// assert_eq!(EnvType::Prod, env);
+    /// ```
+    pub fn from_env_keys(keys: &[&str]) -> Self {
+        Self::match_env_keys(keys)
+            .map(|(env, _)| env)
+            .unwrap_or_default()
+    }
+
+    /// EnvType::try_from_env_keys is a strict version of from_env_keys.
+    /// It returns EnvError::EnvVarNotSet if none of the keys are set,
+    /// and EnvError::InvalidEnvValue if the first set key is not parseable.
+    pub fn try_from_env_keys(keys: &[&str]) -> Result<Self, EnvError> {
+        Self::try_match_env_keys(keys).map(|(env, _)| env)
+    }
+
+    /// EnvType::from_env_types is a function that returns the EnvType from AsEnvStr and EnvKey.
+    pub fn from_env_types<S: AsEnvStr, K: EnvKey>(s: S) -> Self {
+        Self::from_str(&s.as_env_str::<K>()).unwrap_or_default()
+    }
+
+    /// EnvType::from_env_types_prefixed is a version of from_env_types with the key prefixed,
+    /// e.g. "MYAPP_ENV" for the prefix "MYAPP_" and EnvType as the key, when services share a source.
+    /// The default environment type is Dev.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::EnvType;
+    ///
+    /// std::env::set_var("MYAPP_ENV", "test");
+    /// let env = EnvType::from_env_types_prefixed::<EnvType, EnvType>(EnvType::default(), "MYAPP_");
+    /// assert_eq!(EnvType::Test, env);
+    /// ```
+    pub fn from_env_types_prefixed<S: AsEnvStrByKey, K: EnvKey>(s: S, prefix: &str) -> Self {
+        Self::from_str(&s.as_env_str_by_key(&format!("{prefix}{}", K::key()))).unwrap_or_default()
+    }
+
+    /// EnvType::from_async_env_types is the async version of from_env_types, the source is awaited.
+    /// The default environment type is Dev.
+    #[cfg(feature = "async")]
+    pub async fn from_async_env_types<S: AsyncEnvStr, K: EnvKey>(s: S) -> Self {
+        Self::from_str(&s.as_env_str::<K>().await).unwrap_or_default()
+    }
+
+    /// EnvType::from_env_str is a function that returns the environment type from the string.
+    /// The default environment type is Dev.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use env_type::types::{EnvType, AsEnvTypeStr};
+    /// use std::str::FromStr;
+    ///
+    /// struct Config {
+    ///   env_str: String,
+    /// }
+    /// let config = Config {
+    ///  env_str: "Production".to_string(),
+    /// };
+    ///
+    /// impl AsEnvTypeStr for Config {
+    ///   fn as_env_type_str(&self) -> Option<String> {
+    ///     Some(self.env_str.clone())
+    ///   }
+    /// }
+    /// let env = EnvType::from_env_str(config);
+    /// assert_eq!(EnvType::Prod, env);
+    pub fn from_env_str<T: AsEnvTypeStr>(t: T) -> Self {
+        Self::from_str(t.as_env_type_str().unwrap_or_default().as_str()).unwrap_or_default()
+    }
+}
+
+/// The maximum length of input accepted by fuzz_parse.
+/// No environment type spelling is longer, even with surrounding whitespace.
+const MAX_FUZZ_INPUT_LEN: usize = 256;
+
+/// fuzz_parse is a stable entry point for fuzzing the parsing surface, e.g. with `cargo fuzz`.
+/// The bytes are decoded as UTF-8 and parsed leniently.
+/// Invalid UTF-8 and oversized input return None, this never panics.
+///
+/// # Example
+///
+/// ```
+/// use env_type::types::{fuzz_parse, EnvType};
+///
+/// assert_eq!(Some(EnvType::Prod), fuzz_parse(b" prod\n"));
+/// assert_eq!(None, fuzz_parse(&[0xff, 0xfe]));
+/// ```
+pub fn fuzz_parse(data: &[u8]) -> Option<EnvType> {
+    if data.len() > MAX_FUZZ_INPUT_LEN {
+        return None;
+    }
+    std::str::from_utf8(data)
+        .ok()
+        .and_then(|s| EnvType::from_str_lenient(s).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ScopedEnv;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_env_type() {
+        assert_eq!(EnvType::from_str("Develop").unwrap(), EnvType::Dev);
+        assert_eq!(EnvType::from_str("develop").unwrap(), EnvType::Dev);
+        assert_eq!(EnvType::from_str("DEV").unwrap(), EnvType::Dev);
+        assert_eq!(EnvType::from_str("Dev").unwrap(), EnvType::Dev);
+        assert_eq!(EnvType::from_str("dev").unwrap(), EnvType::Dev);
+        assert_eq!(EnvType::from_str("D").unwrap(), EnvType::Dev);
+        assert_eq!(EnvType::from_str("d").unwrap(), EnvType::Dev);
+        assert_eq!(EnvType::from_str("TEST").unwrap(), EnvType::Test);
+        assert_eq!(EnvType::from_str("Test").unwrap(), EnvType::Test);
+        assert_eq!(EnvType::from_str("test").unwrap(), EnvType::Test);
+        assert_eq!(EnvType::from_str("T").unwrap(), EnvType::Test);
+        assert_eq!(EnvType::from_str("t").unwrap(), EnvType::Test);
+        assert_eq!(EnvType::from_str("Staging").unwrap(), EnvType::Stg);
+        assert_eq!(EnvType::from_str("staging").unwrap(), EnvType::Stg);
+        assert_eq!(EnvType::from_str("STG").unwrap(), EnvType::Stg);
+        assert_eq!(EnvType::from_str("Stg").unwrap(), EnvType::Stg);
+        assert_eq!(EnvType::from_str("stg").unwrap(), EnvType::Stg);
+        assert_eq!(EnvType::from_str("S").unwrap(), EnvType::Stg);
+        assert_eq!(EnvType::from_str("s").unwrap(), EnvType::Stg);
+        assert_eq!(EnvType::from_str("Production").unwrap(), EnvType::Prod);
+        assert_eq!(EnvType::from_str("production").unwrap(), EnvType::Prod);
+        assert_eq!(EnvType::from_str("PROD").unwrap(), EnvType::Prod);
+        assert_eq!(EnvType::from_str("Prod").unwrap(), EnvType::Prod);
+        assert_eq!(EnvType::from_str("prod").unwrap(), EnvType::Prod);
+        assert_eq!(EnvType::from_str("P").unwrap(), EnvType::Prod);
+        assert_eq!(EnvType::from_str("p").unwrap(), EnvType::Prod);
+        assert_eq!(EnvType::from_str("local").unwrap(), EnvType::Local);
+        assert_eq!(EnvType::from_str("Local").unwrap(), EnvType::Local);
+        assert_eq!(EnvType::from_str("LOCAL").unwrap(), EnvType::Local);
+        assert_eq!(EnvType::from_str("l").unwrap(), EnvType::Local);
+        assert_eq!(EnvType::from_str("L").unwrap(), EnvType::Local);
+        assert_eq!(EnvType::from_str("ci").unwrap(), EnvType::Ci);
+        assert_eq!(EnvType::from_str("Ci").unwrap(), EnvType::Ci);
+        assert_eq!(EnvType::from_str("CI").unwrap(), EnvType::Ci);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(EnvType::parse("dev").unwrap(), EnvType::Dev);
+        assert_eq!(EnvType::parse("Production").unwrap(), EnvType::Prod);
+        let err = EnvType::parse("banana").unwrap_err();
+        assert_eq!(
+            err,
+            EnvError::ParseError {
+                value: "banana".to_string()
+            }
+        );
+        assert!(err.to_string().contains("banana"));
+        // exact like FromStr, no trimming
+        assert!(EnvType::parse(" dev ").is_err());
+    }
+
+    #[test]
+    fn test_from_str_lenient() {
+        assert_eq!(EnvType::from_str_lenient("  dev  ").unwrap(), EnvType::Dev);
+        assert_eq!(
+            EnvType::from_str_lenient("PRODUCTION").unwrap(),
+            EnvType::Prod
+        );
+        assert_eq!(EnvType::from_str_lenient("\tStg\n").unwrap(), EnvType::Stg);
+        assert_eq!(EnvType::from_str_lenient("T").unwrap(), EnvType::Test);
+        assert_eq!(
+            EnvType::from_str_lenient(""),
+            Err(EnvError::ParseError {
+                value: "".to_string()
+            })
+        );
+        assert_eq!(
+            EnvType::from_str_lenient(" garbage "),
+            Err(EnvError::ParseError {
+                value: " garbage ".to_string()
+            })
+        );
+        // strict parsing is unchanged
+        assert!(EnvType::from_str(" dev ").is_err());
+        assert!(EnvType::from_str("PRODUCTION").is_err());
+    }
+
+    #[test]
+    fn test_from_str_lossy() {
+        assert_eq!(EnvType::from_str_lossy("stg"), (EnvType::Stg, None));
+        assert_eq!(
+            EnvType::from_str_lossy("demo"),
+            (EnvType::Dev, Some("demo".to_string()))
+        );
+        assert_eq!(
+            EnvType::from_str_lossy(""),
+            (EnvType::Dev, Some("".to_string()))
+        );
+
+        // unknown environments can be kept as Custom
+        let preview = EnvType::Custom("pr-1234");
+        assert!(preview.is_custom());
+        assert_eq!(preview, EnvType::Custom("pr-1234"));
+        assert_ne!(preview, EnvType::Custom("demo"));
+        assert_ne!(preview, EnvType::Dev);
+        assert!(!EnvType::VARIANTS.contains(&preview));
+    }
+
+    #[test]
+    fn test_fuzz_parse() {
+        assert_eq!(fuzz_parse(b"Production"), Some(EnvType::Prod));
+        assert_eq!(fuzz_parse(b"\tci "), Some(EnvType::Ci));
+        assert_eq!(fuzz_parse(b""), None);
+        assert_eq!(fuzz_parse(&[0xc3, 0x28]), None);
+        assert_eq!(fuzz_parse(&[0x80; 16]), None);
+
+        let mut oversized = vec![b' '; MAX_FUZZ_INPUT_LEN];
+        oversized.extend_from_slice(b"dev");
+        assert_eq!(fuzz_parse(&oversized), None);
+        assert_eq!(fuzz_parse(&vec![b'd'; 1 << 20]), None);
+    }
+
+    #[test]
+    fn test_is_debug() {
+        assert!(EnvType::Dev.is_dev());
         assert!(!EnvType::Test.is_dev());
         assert!(!EnvType::Stg.is_dev());
         assert!(!EnvType::Prod.is_dev());
@@ -292,12 +1291,159 @@ mod tests {
         assert!(EnvType::Test.is_test());
         assert!(EnvType::Stg.is_stg());
         assert!(EnvType::Prod.is_prod());
+        assert!(EnvType::Local.is_local());
+        assert!(EnvType::Ci.is_ci());
+        assert!(!EnvType::Local.is_dev());
+        assert!(!EnvType::Ci.is_test());
+    }
+
+    #[test]
+    fn test_verbose_label() {
+        assert_eq!(EnvType::Dev.verbose_label(), "develop(d)");
+        assert_eq!(EnvType::Test.verbose_label(), "test(t)");
+        assert_eq!(EnvType::Stg.verbose_label(), "staging(s)");
+        assert_eq!(EnvType::Prod.verbose_label(), "production(p)");
+        assert_eq!(EnvType::Local.verbose_label(), "local(l)");
+        assert_eq!(EnvType::Ci.verbose_label(), "ci(ci)");
+        assert_eq!(EnvType::Custom("demo").verbose_label(), "demo(demo)");
+
+        for env in EnvType::VARIANTS {
+            assert_eq!(EnvType::from_str(env.short_str()).unwrap(), *env);
+        }
+    }
+
+    #[test]
+    fn test_try_from_integer() {
+        for u in 0..=3u8 {
+            assert_eq!(EnvType::try_from(u).unwrap().ordinal(), Some(u));
+            assert_eq!(
+                EnvType::try_from(u32::from(u)).unwrap(),
+                EnvType::VARIANTS[u as usize]
+            );
+        }
+        assert_eq!(EnvType::try_from(0u8).unwrap(), EnvType::Dev);
+        assert_eq!(EnvType::try_from(3u8).unwrap(), EnvType::Prod);
+        assert_eq!(
+            EnvType::try_from(4u8),
+            Err(EnvError::ParseError {
+                value: "4".to_string()
+            })
+        );
+        assert!(EnvType::try_from(5u32).is_err());
+        assert!(EnvType::try_from(u32::MAX).is_err());
+        assert_eq!(EnvType::Local.ordinal(), None);
+        assert_eq!(EnvType::Ci.ordinal(), None);
+        assert_eq!(EnvType::Custom("demo").ordinal(), None);
+    }
+
+    #[test]
+    fn test_canonical_names() {
+        let names = EnvType::canonical_names();
+        assert_eq!(names, ["develop", "test", "staging", "production"]);
+        let promotion = [EnvType::Dev, EnvType::Test, EnvType::Stg, EnvType::Prod];
+        for (name, env) in names.iter().zip(&promotion) {
+            assert_eq!(EnvType::from_str(name).unwrap(), *env);
+        }
+    }
+
+    #[test]
+    fn test_all() {
+        let all = EnvType::all();
+        assert_eq!(all.len(), 6);
+        for env in EnvType::VARIANTS {
+            assert_eq!(all.iter().filter(|e| *e == env).count(), 1);
+        }
+        assert_eq!(EnvType::iter().collect::<Vec<_>>(), all);
+    }
+
+    #[test]
+    fn test_indexed_all() {
+        assert_eq!(
+            EnvType::indexed_all(),
+            [
+                (0, EnvType::Dev, "develop"),
+                (1, EnvType::Test, "test"),
+                (2, EnvType::Stg, "staging"),
+                (3, EnvType::Prod, "production"),
+                (4, EnvType::Local, "local"),
+                (5, EnvType::Ci, "ci"),
+            ]
+        );
+        for (_, env, name) in EnvType::indexed_all() {
+            assert_eq!(EnvType::from_str(name).unwrap(), env);
+        }
+    }
+
+    #[test]
+    fn test_env_error() {
+        assert_eq!(
+            EnvError::NoCurrentEnv.to_string(),
+            "No current environment specified"
+        );
+        assert_eq!(
+            EnvError::EnvVarNotSet {
+                key: "ENV".to_string()
+            }
+            .to_string(),
+            "Environment variable not set: ENV"
+        );
+        assert_eq!(
+            EnvError::InvalidEnvValue {
+                key: "ENV".to_string(),
+                value: "xyz".to_string()
+            }
+            .to_string(),
+            "Invalid environment value for ENV: xyz"
+        );
+        assert_eq!(
+            EnvError::ParseError {
+                value: "xyz".to_string()
+            }
+            .to_string(),
+            "Failed to parse environment type: xyz"
+        );
+
+        assert_eq!(
+            EnvError::ContextValueNotFound {
+                context: "app::UrlContext".to_string(),
+                env: EnvType::Stg,
+                tried: vec![EnvType::Stg, EnvType::Prod]
+            }
+            .to_string(),
+            "Context value not found for app::UrlContext in Stg, tried: [Stg, Prod]"
+        );
+
+        fn boxed() -> Result<(), Box<dyn std::error::Error>> {
+            Err(EnvError::ContextNotFound)?
+        }
+        let err = boxed().unwrap_err();
+        assert_eq!(err.to_string(), "Context not found for type");
+        assert_eq!(
+            err.downcast_ref::<EnvError>(),
+            Some(&EnvError::ContextNotFound)
+        );
+    }
+
+    #[test]
+    fn test_current() {
+        let mut guard = ScopedEnv::set("ENV", "s");
+        EnvType::reset_current();
+        assert_eq!(EnvType::current(), EnvType::Stg);
+
+        // cached
+        guard.set_var("ENV", "p");
+        assert_eq!(EnvType::current(), EnvType::Stg);
+
+        // re-resolved after reset
+        EnvType::reset_current();
+        assert_eq!(EnvType::current(), EnvType::Prod);
+        EnvType::reset_current();
     }
 
     #[test]
     fn test_from_env() {
-        std::env::set_var("ENV", "d");
-        assert_eq!(EnvType::from_env(), EnvType::Dev);
+        let mut guard = ScopedEnv::set("ENV", "p");
+        assert_eq!(EnvType::from_env(), EnvType::Prod);
 
         struct TestEnv;
         impl EnvKey for TestEnv {
@@ -305,12 +1451,426 @@ mod tests {
                 "TEST_ENV"
             }
         }
-        std::env::set_var("TEST_ENV", "t");
+        guard.set_var("TEST_ENV", "t");
         assert_eq!(EnvType::from_env_key::<TestEnv>(), EnvType::Test);
         // fallback to default
+        guard.remove_var("ENV");
         assert_eq!(EnvType::from_env_key::<EnvType>(), EnvType::Dev);
     }
 
+    #[test]
+    fn test_map_env() {
+        struct ServiceEnv;
+        impl EnvKey for ServiceEnv {
+            fn key() -> &'static str {
+                "SERVICE_ENV"
+            }
+        }
+
+        let map = MapEnv(HashMap::from([
+            ("ENV".to_string(), "prod".to_string()),
+            ("SERVICE_ENV".to_string(), "stg".to_string()),
+        ]));
+        assert_eq!(
+            EnvType::from_env_types::<MapEnv, EnvType>(map.clone()),
+            EnvType::Prod
+        );
+        assert_eq!(
+            EnvType::from_env_types::<MapEnv, ServiceEnv>(map),
+            EnvType::Stg
+        );
+        assert_eq!(
+            EnvType::from_env_types::<MapEnv, EnvType>(MapEnv::default()),
+            EnvType::Dev
+        );
+    }
+
+    #[test]
+    fn test_std_maps() {
+        let owned = HashMap::from([("ENV".to_string(), "prod".to_string())]);
+        let borrowed = HashMap::from([("ENV", "stg".to_string())]);
+        let tree = BTreeMap::from([("ENV".to_string(), "test".to_string())]);
+        let pairs = vec![
+            ("ENV".to_string(), "ci".to_string()),
+            ("ENV".to_string(), "prod".to_string()),
+        ];
+        assert_eq!(
+            EnvType::from_env_types::<_, EnvType>(owned.clone()),
+            EnvType::Prod
+        );
+        assert_eq!(
+            EnvType::from_env_types::<_, EnvType>(borrowed.clone()),
+            EnvType::Stg
+        );
+        assert_eq!(
+            EnvType::from_env_types::<_, EnvType>(tree.clone()),
+            EnvType::Test
+        );
+        assert_eq!(
+            EnvType::from_env_types::<&[_], EnvType>(&pairs),
+            EnvType::Ci
+        );
+
+        // missing keys are empty, so the default environment type is used
+        assert_eq!(owned.as_env_str_by_key("APP_ENV"), "");
+        assert_eq!(borrowed.as_env_str_by_key("APP_ENV"), "");
+        assert_eq!(tree.as_env_str_by_key("APP_ENV"), "");
+        assert_eq!(pairs.as_slice().as_env_str_by_key("APP_ENV"), "");
+        assert_eq!(
+            EnvType::from_env_types::<_, EnvType>(HashMap::<String, String>::new()),
+            EnvType::Dev
+        );
+
+        // keys are case-sensitive, like the process environment variables
+        let lower = HashMap::from([("env".to_string(), "prod".to_string())]);
+        let lower_pairs = vec![("env".to_string(), "prod".to_string())];
+        assert_eq!(lower.as_env_str::<EnvType>(), "");
+        assert_eq!(lower_pairs.as_slice().as_env_str::<EnvType>(), "");
+        assert_eq!(
+            EnvType::from_env_types::<_, EnvType>(BTreeMap::from([(
+                "env".to_string(),
+                "prod".to_string()
+            )])),
+            EnvType::Dev
+        );
+    }
+
+    #[test]
+    fn test_from_env_or() {
+        let mut guard = ScopedEnv::unset("ENV");
+        assert_eq!(EnvType::from_env_or(EnvType::Prod), EnvType::Prod);
+        guard.set_var("ENV", "dev");
+        assert_eq!(EnvType::from_env_or(EnvType::Prod), EnvType::Dev);
+        guard.set_var("ENV", "unknown");
+        assert_eq!(EnvType::from_env_or(EnvType::Prod), EnvType::Prod);
+
+        struct FallbackEnv;
+        impl EnvKey for FallbackEnv {
+            fn key() -> &'static str {
+                "TEST_FALLBACK_ENV"
+            }
+        }
+        guard.remove_var("TEST_FALLBACK_ENV");
+        assert_eq!(
+            EnvType::from_env_key_or::<FallbackEnv>(EnvType::Stg),
+            EnvType::Stg
+        );
+        guard.set_var("TEST_FALLBACK_ENV", "test");
+        assert_eq!(
+            EnvType::from_env_key_or::<FallbackEnv>(EnvType::Stg),
+            EnvType::Test
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_from_async_env_types() {
+        use std::future::Future;
+        use std::task::{Context, Poll, Waker};
+
+        struct StaticSource;
+
+        #[async_trait::async_trait]
+        impl AsyncEnvStr for StaticSource {
+            async fn as_env_str<T: EnvKey>(&self) -> String {
+                "prod".to_string()
+            }
+        }
+
+        // the source is ready immediately, so a single poll completes the future
+        let mut future = std::pin::pin!(EnvType::from_async_env_types::<_, EnvType>(StaticSource));
+        let poll = future
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()));
+        assert_eq!(poll, Poll::Ready(EnvType::Prod));
+    }
+
+    #[test]
+    fn test_from_env_types_prefixed() {
+        let _guard = ScopedEnv::set("TEST_MYAPP_ENV", "test");
+        assert_eq!(
+            EnvType::from_env_types_prefixed::<EnvType, EnvType>(EnvType::default(), "TEST_MYAPP_"),
+            EnvType::Test
+        );
+        assert_eq!(
+            EnvType::from_env_types_prefixed::<EnvType, EnvType>(EnvType::default(), "TEST_OTHER_"),
+            EnvType::Dev
+        );
+
+        let map = MapEnv(HashMap::from([(
+            "MYAPP_ENV".to_string(),
+            "prod".to_string(),
+        )]));
+        assert_eq!(
+            EnvType::from_env_types_prefixed::<MapEnv, EnvType>(map, "MYAPP_"),
+            EnvType::Prod
+        );
+    }
+
+    #[test]
+    fn test_from_node_env() {
+        let mut guard = ScopedEnv::unset("NODE_ENV");
+        for (value, expected) in [
+            ("production", EnvType::Prod),
+            ("development", EnvType::Dev),
+            ("test", EnvType::Test),
+            ("staging", EnvType::Dev),
+        ] {
+            guard.set_var("NODE_ENV", value);
+            assert_eq!(EnvType::from_node_env(), expected, "{value}");
+        }
+        guard.remove_var("NODE_ENV");
+        assert_eq!(EnvType::from_node_env(), EnvType::Dev);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_from_env_os() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        struct OsEnv;
+        impl EnvKey for OsEnv {
+            fn key() -> &'static str {
+                "TEST_OS_ENV"
+            }
+        }
+
+        let mut guard = ScopedEnv::set("TEST_OS_ENV", "stg");
+        assert_eq!(EnvType::from_env_os::<OsEnv>(), EnvType::Stg);
+
+        guard.set_var("TEST_OS_ENV", OsStr::from_bytes(b"prod\xff"));
+        assert_eq!(EnvType::from_env_key::<OsEnv>(), EnvType::Dev);
+        assert_eq!(EnvType::from_env_os::<OsEnv>(), EnvType::Prod);
+
+        guard.set_var("TEST_OS_ENV", OsStr::from_bytes(b"pr\xffod"));
+        assert_eq!(EnvType::from_env_os::<OsEnv>(), EnvType::Dev);
+
+        guard.remove_var("TEST_OS_ENV");
+        assert_eq!(EnvType::from_env_os::<OsEnv>(), EnvType::Dev);
+    }
+
+    #[test]
+    fn test_try_from_env_key() {
+        struct StrictEnv;
+        impl EnvKey for StrictEnv {
+            fn key() -> &'static str {
+                "TEST_STRICT_ENV"
+            }
+        }
+
+        let mut guard = ScopedEnv::unset("TEST_STRICT_ENV");
+        assert!(matches!(
+            EnvType::try_from_env_key::<StrictEnv>(),
+            Err(EnvError::EnvVarNotSet { key }) if key == "TEST_STRICT_ENV"
+        ));
+
+        guard.set_var("TEST_STRICT_ENV", "unknown");
+        assert!(matches!(
+            EnvType::try_from_env_key::<StrictEnv>(),
+            Err(EnvError::InvalidEnvValue { key, value }) if key == "TEST_STRICT_ENV" && value == "unknown"
+        ));
+
+        guard.set_var("TEST_STRICT_ENV", "stg");
+        assert_eq!(
+            EnvType::try_from_env_key::<StrictEnv>().unwrap(),
+            EnvType::Stg
+        );
+    }
+
+    #[test]
+    fn test_from_env_very_strict() {
+        struct VeryStrictEnv;
+        impl EnvKey for VeryStrictEnv {
+            fn key() -> &'static str {
+                "TEST_VERY_STRICT_ENV"
+            }
+        }
+
+        let mut guard = ScopedEnv::set("TEST_VERY_STRICT_ENV", "production");
+        assert_eq!(
+            EnvType::from_env_very_strict::<VeryStrictEnv>().unwrap(),
+            EnvType::Prod
+        );
+
+        guard.set_var("TEST_VERY_STRICT_ENV", "prod");
+        assert_eq!(
+            EnvType::from_env_very_strict::<VeryStrictEnv>(),
+            Err(EnvError::NonCanonical {
+                input: "prod".to_string()
+            })
+        );
+
+        guard.set_var("TEST_VERY_STRICT_ENV", "xyz");
+        assert_eq!(
+            EnvType::from_env_very_strict::<VeryStrictEnv>(),
+            Err(EnvError::InvalidEnvValue {
+                key: "TEST_VERY_STRICT_ENV".to_string(),
+                value: "xyz".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_match_env_keys() {
+        let keys = [
+            "TEST_CHAIN_ENV",
+            "TEST_CHAIN_APP_ENV",
+            "TEST_CHAIN_RUST_ENV",
+        ];
+        let mut guard = ScopedEnv::unset(keys[0]);
+        for key in keys {
+            guard.remove_var(key);
+        }
+
+        // all missing
+        assert_eq!(EnvType::match_env_keys(&keys), None);
+        assert_eq!(
+            EnvType::try_match_env_keys(&keys),
+            Err(EnvError::EnvVarNotSet {
+                key: "TEST_CHAIN_ENV, TEST_CHAIN_APP_ENV, TEST_CHAIN_RUST_ENV".to_string()
+            })
+        );
+
+        // gap in the chain
+        guard.set_var("TEST_CHAIN_RUST_ENV", "p");
+        assert_eq!(
+            EnvType::match_env_keys(&keys),
+            Some((EnvType::Prod, "TEST_CHAIN_RUST_ENV"))
+        );
+        assert_eq!(
+            EnvType::try_match_env_keys(&keys),
+            Ok((EnvType::Prod, "TEST_CHAIN_RUST_ENV"))
+        );
+
+        // unparseable value is skipped, but errors in strict mode
+        guard.set_var("TEST_CHAIN_APP_ENV", "unknown");
+        assert_eq!(
+            EnvType::match_env_keys(&keys),
+            Some((EnvType::Prod, "TEST_CHAIN_RUST_ENV"))
+        );
+        assert_eq!(
+            EnvType::try_match_env_keys(&keys),
+            Err(EnvError::InvalidEnvValue {
+                key: "TEST_CHAIN_APP_ENV".to_string(),
+                value: "unknown".to_string()
+            })
+        );
+
+        // precedence
+        guard.set_var("TEST_CHAIN_ENV", "t");
+        assert_eq!(
+            EnvType::match_env_keys(&keys),
+            Some((EnvType::Test, "TEST_CHAIN_ENV"))
+        );
+        assert_eq!(
+            EnvType::try_match_env_keys(&keys),
+            Ok((EnvType::Test, "TEST_CHAIN_ENV"))
+        );
+    }
+
+    #[test]
+    fn test_from_env_keys() {
+        let keys = ["TEST_KEYS_ENV", "TEST_KEYS_APP_ENV"];
+        let mut guard = ScopedEnv::unset(keys[0]);
+        for key in keys {
+            guard.remove_var(key);
+        }
+        assert_eq!(EnvType::from_env_keys(&keys), EnvType::Dev);
+        assert!(matches!(
+            EnvType::try_from_env_keys(&keys),
+            Err(EnvError::EnvVarNotSet { .. })
+        ));
+
+        guard.set_var("TEST_KEYS_APP_ENV", "s");
+        assert_eq!(EnvType::from_env_keys(&keys), EnvType::Stg);
+        assert_eq!(EnvType::try_from_env_keys(&keys), Ok(EnvType::Stg));
+
+        // the first listed key wins
+        guard.set_var("TEST_KEYS_ENV", "p");
+        assert_eq!(EnvType::from_env_keys(&keys), EnvType::Prod);
+        assert_eq!(EnvType::try_from_env_keys(&keys), Ok(EnvType::Prod));
+        assert_eq!(
+            EnvType::from_env_keys(&["TEST_KEYS_APP_ENV", "TEST_KEYS_ENV"]),
+            EnvType::Stg
+        );
+    }
+
+    #[cfg(feature = "clap")]
+    #[test]
+    fn test_clap_value_enum() {
+        use clap::{Arg, Command};
+
+        let command = Command::new("app").arg(
+            Arg::new("env")
+                .long("env")
+                .value_parser(clap::builder::EnumValueParser::<EnvType>::new()),
+        );
+        let parse = |value: &str| {
+            command
+                .clone()
+                .try_get_matches_from(["app", "--env", value])
+                .map(|matches| *matches.get_one::<EnvType>("env").unwrap())
+        };
+
+        assert_eq!(parse("prod").unwrap(), EnvType::Prod);
+        assert_eq!(parse("p").unwrap(), EnvType::Prod);
+        assert_eq!(parse("production").unwrap(), EnvType::Prod);
+        assert_eq!(parse("DEV").unwrap(), EnvType::Dev);
+        assert!(parse("unknown").is_err());
+
+        let help = command.clone().render_help().to_string();
+        assert!(help.contains("develop"));
+        assert!(help.contains("production"));
+        // aliases are accepted but hidden
+        assert!(!help.contains("PROD"));
+    }
+
+    #[cfg(feature = "clap")]
+    #[test]
+    fn test_clap_derive() {
+        use clap::{CommandFactory, Parser};
+
+        #[derive(Parser)]
+        struct Cli {
+            #[arg(long, value_enum, default_value_t = EnvType::Dev)]
+            env: EnvType,
+        }
+
+        assert_eq!(Cli::parse_from(["app", "--env", "p"]).env, EnvType::Prod);
+        assert_eq!(
+            Cli::parse_from(["app", "--env", "staging"]).env,
+            EnvType::Stg
+        );
+        assert_eq!(Cli::parse_from(["app"]).env, EnvType::Dev);
+        assert!(Cli::try_parse_from(["app", "--env", "x"]).is_err());
+
+        let help = Cli::command().render_help().to_string();
+        assert!(help.contains("develop, test, staging, production"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        for env in EnvType::VARIANTS {
+            let json = serde_json::to_string(env).unwrap();
+            assert_eq!(json, format!("\"{}\"", env.canonical_str()));
+            assert_eq!(serde_json::from_str::<EnvType>(&json).unwrap(), *env);
+        }
+        assert_eq!(
+            serde_json::from_str::<EnvType>("\"prod\"").unwrap(),
+            EnvType::Prod
+        );
+        assert_eq!(
+            serde_json::from_str::<EnvType>("\"p\"").unwrap(),
+            EnvType::Prod
+        );
+
+        let err = serde_json::from_str::<EnvType>("\"unknown\"").unwrap_err();
+        assert_eq!(err.to_string(), "Failed to parse environment type: unknown");
+        assert!(serde_json::from_str::<EnvType>("1").is_err());
+    }
+
     #[test]
     fn test_from_env_str() {
         struct TestEnv(&'static str);
@@ -0,0 +1,118 @@
+//! This is used to share the environment as web framework state, like axum's `State` or actix's `Data`.
+//! The environment is wrapped in `Arc`, so cloning the state is cheap.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use axum::{extract::State, routing::get, Router};
+//! use env_type::environment::{Environment, EnvironmentBuilder};
+//! use env_type::is_debug::IsDebug;
+//! use env_type::types::EnvType;
+//! use env_type::web::EnvironmentState;
+//! use std::sync::Arc;
+//!
+//! async fn handler(State(state): State<Arc<Environment>>) -> String {
+//!     format!("{:?} debug={}", state.environment().current_env(), state.is_debug())
+//! }
+//!
+//! let env = EnvironmentBuilder::default()
+//!     .current_env(EnvType::from_env())
+//!     .build()
+//!     .unwrap();
+//! let app: Router = Router::new()
+//!     .route("/", get(handler))
+//!     .with_state(env.as_state());
+//! ```
+use crate::environment::Environment;
+use std::sync::Arc;
+
+/// EnvironmentState is a trait to access the environment from web framework state.
+/// This is implemented for any type that can be referenced as an Environment,
+/// e.g. `Environment` and `Arc<Environment>`.
+/// Application state structs can implement `AsRef<Environment>` to get this trait.
+///
+/// # Example
+///
+/// ```
+/// use env_type::environment::{Environment, EnvironmentBuilder};
+/// use env_type::types::EnvType;
+/// use env_type::web::EnvironmentState;
+///
+/// #[derive(Clone)]
+/// struct AppState {
+///     env: std::sync::Arc<Environment>,
+/// }
+///
+/// impl AsRef<Environment> for AppState {
+///     fn as_ref(&self) -> &Environment {
+///         &self.env
+///     }
+/// }
+///
+/// let env = EnvironmentBuilder::default()
+///     .current_env(EnvType::Prod)
+///     .build()
+///     .unwrap();
+/// let state = AppState { env: env.as_state() };
+/// assert_eq!(EnvType::Prod, state.environment().current_env());
+/// ```
+pub trait EnvironmentState {
+    fn environment(&self) -> &Environment;
+}
+
+impl<T: AsRef<Environment>> EnvironmentState for T {
+    fn environment(&self) -> &Environment {
+        self.as_ref()
+    }
+}
+
+impl AsRef<Environment> for Environment {
+    fn as_ref(&self) -> &Environment {
+        self
+    }
+}
+
+impl Environment {
+    /// Wrap the environment in `Arc` to use it as shared web framework state.
+    pub fn as_state(self) -> Arc<Environment> {
+        Arc::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{ContextBuilder, ContextMarker};
+    use crate::environment::EnvironmentBuilder;
+    use crate::types::EnvType;
+
+    struct UrlContext;
+
+    impl ContextMarker for UrlContext {
+        type Value = String;
+    }
+
+    #[test]
+    fn test_as_state() {
+        let env = EnvironmentBuilder::default()
+            .current_env(EnvType::Stg)
+            .with_context(
+                ContextBuilder::<UrlContext>::default()
+                    .with_value(EnvType::Stg, "https://stg.example.com".to_string())
+                    .build(),
+            )
+            .build()
+            .unwrap();
+
+        let state = env.as_state();
+        let cloned = Arc::clone(&state);
+
+        for state in [state, cloned] {
+            assert_eq!(EnvType::Stg, state.environment().current_env());
+            assert_eq!(
+                Some("https://stg.example.com".to_string()),
+                state.environment().current_value::<UrlContext>()
+            );
+        }
+    }
+}
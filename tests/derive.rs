@@ -0,0 +1,38 @@
+#![cfg(feature = "derive")]
+
+use env_type::types::EnvKey;
+
+#[derive(EnvKey)]
+#[env_key("TEST_DERIVE_PAYMENTS_ENV")]
+struct PaymentsEnv;
+
+#[derive(EnvKey)]
+struct TestDeriveBillingEnv;
+
+#[test]
+fn test_derive_env_key() {
+    assert_eq!(PaymentsEnv::key(), "TEST_DERIVE_PAYMENTS_ENV");
+    assert_eq!(TestDeriveBillingEnv::key(), "TEST_DERIVE_BILLING_ENV");
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn test_derive_env_key_from_env() {
+    use env_type::testing::ScopedEnv;
+    use env_type::types::EnvType;
+
+    let mut guard = ScopedEnv::set("TEST_DERIVE_PAYMENTS_ENV", "prod");
+    assert_eq!(EnvType::from_env_key::<PaymentsEnv>(), EnvType::Prod);
+
+    guard.set_var("TEST_DERIVE_BILLING_ENV", "stg");
+    assert_eq!(
+        EnvType::from_env_key::<TestDeriveBillingEnv>(),
+        EnvType::Stg
+    );
+}
+
+#[test]
+fn test_derive_env_key_ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
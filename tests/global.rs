@@ -0,0 +1,61 @@
+//! The global environment is set once per process, so its test runs in its own binary.
+use env_type::environment::{Environment, EnvironmentBuilder};
+use env_type::is_debug::{debug_context, IsDebug, IsDebugContext};
+use env_type::types::{EnvError, EnvType};
+use std::thread;
+
+#[test]
+fn test_global() {
+    assert!(Environment::try_global().is_none());
+
+    // only one of the concurrent initializations wins
+    let handles: Vec<_> = [EnvType::Dev, EnvType::Prod]
+        .into_iter()
+        .map(|env| {
+            thread::spawn(move || {
+                let environment = EnvironmentBuilder::default()
+                    .current_env(env)
+                    .with_context(debug_context().build())
+                    .build()
+                    .unwrap();
+                Environment::init_global(environment).is_ok()
+            })
+        })
+        .collect();
+    let initialized = handles
+        .into_iter()
+        .map(|h| h.join().unwrap())
+        .filter(|ok| *ok)
+        .count();
+    assert_eq!(initialized, 1);
+
+    let current = Environment::global().current_env();
+    let second = EnvironmentBuilder::default()
+        .current_env(EnvType::Test)
+        .build()
+        .unwrap();
+    assert!(matches!(
+        Environment::init_global(second),
+        Err(EnvError::GlobalAlreadyInitialized)
+    ));
+    assert_eq!(current, Environment::global().current_env());
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            thread::spawn(|| {
+                let env = Environment::global();
+                (
+                    env.current_env(),
+                    env.is_debug(),
+                    env.current_value::<IsDebugContext>(),
+                )
+            })
+        })
+        .collect();
+    for handle in handles {
+        let (env, is_debug, value) = handle.join().unwrap();
+        assert_eq!(env, current);
+        assert_eq!(is_debug, current.is_dev());
+        assert_eq!(value, Some(current.is_dev()));
+    }
+}
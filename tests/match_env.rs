@@ -0,0 +1,5 @@
+#[test]
+fn test_match_env_ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/match_env/*.rs");
+}
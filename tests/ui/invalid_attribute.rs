@@ -0,0 +1,16 @@
+use env_type::types::EnvKey;
+
+#[derive(EnvKey)]
+#[env_key(NAME)]
+struct IdentEnv;
+
+#[derive(EnvKey)]
+#[env_key("")]
+struct EmptyEnv;
+
+#[derive(EnvKey)]
+#[env_key("FIRST")]
+#[env_key("SECOND")]
+struct DuplicateEnv;
+
+fn main() {}
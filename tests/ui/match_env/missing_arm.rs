@@ -0,0 +1,13 @@
+use env_type::match_env;
+use env_type::types::EnvType;
+
+fn main() {
+    let _ = match_env!(EnvType::Dev,
+        dev => 1,
+        test => 2,
+        prod => 4,
+        local => 5,
+        ci => 6,
+        custom => 0,
+    );
+}
@@ -0,0 +1,13 @@
+use env_type::types::EnvKey;
+
+#[derive(EnvKey)]
+struct NamedEnv {
+    name: String,
+}
+
+#[derive(EnvKey)]
+enum EnumEnv {
+    Dev,
+}
+
+fn main() {}